@@ -1,18 +1,28 @@
 use {
-    crate::interface::{BoomerangTestClient, BoomerangTestClientConfig},
+    crate::{
+        error::BoomerangClientError,
+        fixtures::{load_account_fixture, load_account_fixture_dir},
+        interface::{
+            BoomerangTestClient, BoomerangTestClientConfig, ProcessedTransaction, SimulationResult,
+        },
+    },
     async_trait::async_trait,
     solana_program_test::{ProgramTest, ProgramTestBanksClientExt, ProgramTestContext},
     solana_sdk::{
-        account::Account,
+        account::{Account, AccountSharedData},
+        clock::{Clock, Slot, UnixTimestamp},
+        epoch_schedule::EpochSchedule,
         hash::Hash,
         pubkey::Pubkey,
         signature::{Keypair, Signature},
-        slot_hashes::SlotHashes,
-        transaction::{Transaction, TransactionError},
+        signer::Signer,
+        system_instruction, system_program,
+        transaction::Transaction,
     },
 };
 
 pub struct BoomerangBanksClient {
+    payer: Keypair,
     program_id: Pubkey,
     program_test_context: ProgramTestContext,
 }
@@ -20,24 +30,96 @@ pub struct BoomerangBanksClient {
 impl BoomerangBanksClient {
     pub async fn setup(config: &BoomerangTestClientConfig) -> Self {
         let program_id = config.program_id;
+        let payer = config.payer();
 
         let mut program_test = ProgramTest::new(&config.program_file, config.program_id, None);
+        config
+            .extra_programs
+            .iter()
+            .for_each(|(program_file, program_id)| {
+                program_test.add_program(program_file, *program_id, None);
+            });
+        if let Some(account_dir) = &config.account_dir {
+            load_account_fixture_dir(account_dir)
+                .into_iter()
+                .for_each(|(address, account)| {
+                    program_test.add_account(address, account);
+                });
+        }
+        config.accounts.iter().for_each(|(address, dump_path)| {
+            let (_, account) = load_account_fixture(dump_path);
+            program_test.add_account(*address, account);
+        });
         config.features_disabled.iter().for_each(|feature| {
             program_test.deactivate_feature(*feature);
         });
 
-        let program_test_context = program_test.start_with_context().await;
+        config
+            .staged_accounts
+            .iter()
+            .for_each(|(address, account)| {
+                program_test.add_account(*address, account.clone().into());
+            });
+
+        // Fund a dedicated payer rather than using the context's, so it starts
+        // with the same balance as on a test validator
+        if config.payer_lamports > 0 {
+            program_test.add_account(
+                payer.pubkey(),
+                Account::new(config.payer_lamports, 0, &system_program::id()),
+            );
+        }
+
+        let mut program_test_context = program_test.start_with_context().await;
         if config.warp_slot > 0 {
-            let mut slot_hashes = SlotHashes::default();
-            slot_hashes.add(config.warp_slot, Hash::new_unique());
-            program_test_context.set_sysvar(&slot_hashes);
+            // `solana-test-validator --warp-slot` freezes the warp slot and
+            // processes transactions in the slot after it, so mirror that to
+            // give both backends the same `Clock` and `SlotHashes`
+            program_test_context
+                .warp_to_slot(config.warp_slot + 1)
+                .unwrap();
+            program_test_context.last_blockhash = program_test_context
+                .banks_client
+                .get_latest_blockhash()
+                .await
+                .unwrap();
         }
 
         Self {
+            payer,
             program_id,
             program_test_context,
         }
     }
+    /// Transfer lamports to an account from the context's payer, which is the
+    /// genesis mint, so it can fund anything
+    pub(crate) async fn fund_account(
+        &mut self,
+        pubkey: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), BoomerangClientError> {
+        let funder = &self.program_test_context.payer;
+        let recent_blockhash = self
+            .program_test_context
+            .banks_client
+            .get_latest_blockhash()
+            .await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &funder.pubkey(),
+                pubkey,
+                lamports,
+            )],
+            Some(&funder.pubkey()),
+            &[funder],
+            recent_blockhash,
+        );
+        self.program_test_context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.into())
+    }
 }
 
 #[async_trait]
@@ -47,7 +129,7 @@ impl BoomerangTestClient for BoomerangBanksClient {
     }
 
     fn fee_payer(&self) -> Keypair {
-        self.program_test_context.payer.insecure_clone()
+        self.payer.insecure_clone()
     }
 
     fn last_blockhash(&self) -> Hash {
@@ -65,37 +147,188 @@ impl BoomerangTestClient for BoomerangBanksClient {
     async fn process_transaction(
         &mut self,
         transaction: Transaction,
-    ) -> Result<(), Option<TransactionError>> {
+        _signers: &[&Keypair],
+    ) -> Result<(), BoomerangClientError> {
         self.program_test_context
             .banks_client
             .process_transaction(transaction)
             .await
-            .map_err(|err| Some(err.unwrap()))
+            .map_err(|err| err.into())
     }
 
-    async fn poll_for_next_epoch(&self) -> Result<(), Box<dyn std::error::Error>> {
-        unimplemented!() // TODO: Implement for banks client
+    async fn poll_for_next_epoch(&mut self) -> Result<(), BoomerangClientError> {
+        self.advance_epochs(1).await
     }
 
-    async fn poll_slots(&self, _num_slots: u64) -> Result<(), Box<dyn std::error::Error>> {
-        unimplemented!() // TODO: Implement for banks client
+    async fn poll_slots(&mut self, num_slots: u64) -> Result<(), BoomerangClientError> {
+        // Match the RPC backend, which waits until the current slot is
+        // strictly past `num_slots` slots from now
+        let clock = self.get_clock().await?;
+        self.warp_to_slot(clock.slot + num_slots + 1).await
+    }
+
+    async fn get_clock(&mut self) -> Result<Clock, BoomerangClientError> {
+        self.program_test_context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .map_err(|err| err.into())
+    }
+
+    async fn warp_to_slot(&mut self, slot: Slot) -> Result<(), BoomerangClientError> {
+        let current = self.get_clock().await?.slot;
+        if slot <= current {
+            return Err(BoomerangClientError::SlotNotInFuture {
+                current,
+                requested: slot,
+            });
+        }
+        self.program_test_context
+            .warp_to_slot(slot)
+            .map_err(|err| err.into())
+    }
+
+    async fn advance_epochs(&mut self, num_epochs: u64) -> Result<(), BoomerangClientError> {
+        let clock = self.get_clock().await?;
+        let epoch_schedule = self
+            .program_test_context
+            .banks_client
+            .get_sysvar::<EpochSchedule>()
+            .await?;
+        let slot = epoch_schedule.get_first_slot_in_epoch(clock.epoch + num_epochs);
+        self.warp_to_slot(slot).await
+    }
+
+    async fn set_unix_timestamp(
+        &mut self,
+        unix_timestamp: UnixTimestamp,
+    ) -> Result<(), BoomerangClientError> {
+        let mut clock = self.get_clock().await?;
+        clock.unix_timestamp = unix_timestamp;
+        self.program_test_context.set_sysvar(&clock);
+        Ok(())
+    }
+
+    async fn create_funded_keypair(
+        &mut self,
+        lamports: u64,
+    ) -> Result<Keypair, BoomerangClientError> {
+        let keypair = Keypair::new();
+        self.fund_account(&keypair.pubkey(), lamports).await?;
+        Ok(keypair)
+    }
+
+    async fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+        _signers: &[&Keypair],
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        let processed = self
+            .program_test_context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        let (logs, units_consumed, return_data) = processed
+            .metadata
+            .map(|metadata| {
+                (
+                    metadata.log_messages,
+                    Some(metadata.compute_units_consumed),
+                    metadata.return_data,
+                )
+            })
+            .unwrap_or_default();
+        Ok(ProcessedTransaction {
+            result: processed.result,
+            logs,
+            inner_instructions: None,
+            return_data,
+            units_consumed,
+        })
+    }
+
+    async fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+        accounts: &[Pubkey],
+    ) -> Result<SimulationResult, BoomerangClientError> {
+        if !accounts.is_empty() {
+            return Err(BoomerangClientError::Unsupported {
+                operation: "simulate_transaction with post-transaction accounts",
+            });
+        }
+        let simulation = self
+            .program_test_context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await?;
+        let result = simulation.result.ok_or_else(|| {
+            BoomerangClientError::Transport("The bank returned no simulation result".into())
+        })?;
+        // The bank returns no details for transactions that fail before
+        // execution, such as those that fail sanitization
+        let (logs, units_consumed, return_data) = simulation
+            .simulation_details
+            .map(|details| (details.logs, details.units_consumed, details.return_data))
+            .unwrap_or_default();
+        Ok(SimulationResult {
+            result,
+            logs,
+            units_consumed,
+            return_data,
+            accounts: vec![],
+        })
     }
 
     async fn confirm_transaction(
         &self,
         _signature: &Signature,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), BoomerangClientError> {
         Ok(())
     }
 
     async fn get_account(
         &mut self,
         pubkey: &Pubkey,
-    ) -> Result<Option<Account>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<Account>, BoomerangClientError> {
         self.program_test_context
             .banks_client
             .get_account(*pubkey)
             .await
             .map_err(|err| err.into())
     }
+
+    async fn get_balance(&mut self, pubkey: &Pubkey) -> Result<u64, BoomerangClientError> {
+        self.program_test_context
+            .banks_client
+            .get_balance(*pubkey)
+            .await
+            .map_err(|err| err.into())
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        space: usize,
+    ) -> Result<u64, BoomerangClientError> {
+        let rent = self.program_test_context.banks_client.get_rent().await?;
+        Ok(rent.minimum_balance(space))
+    }
+
+    async fn set_account(
+        &mut self,
+        pubkey: &Pubkey,
+        account: &AccountSharedData,
+    ) -> Result<(), BoomerangClientError> {
+        self.program_test_context.set_account(pubkey, account);
+        Ok(())
+    }
+
+    async fn get_program_accounts(
+        &mut self,
+        _program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, BoomerangClientError> {
+        Err(BoomerangClientError::Unsupported {
+            operation: "get_program_accounts",
+        })
+    }
 }
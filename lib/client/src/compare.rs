@@ -0,0 +1,94 @@
+use solana_sdk::{account::Account, pubkey::Pubkey, sysvar};
+
+/// Whether an account's state is expected to match across implementations
+/// and backends.
+/// Executable accounts hold the implementations themselves, and sysvars track
+/// the cluster rather than the program.
+pub(crate) fn is_compared(account: &Option<Account>) -> bool {
+    match account {
+        Some(account) => !account.executable && account.owner != sysvar::id(),
+        None => true,
+    }
+}
+
+/// The parts of an account's state that are compared, leaving out the rent
+/// epoch, which depends on when each bank last collected rent rather than on
+/// the program
+pub(crate) fn account_state(account: Option<Account>) -> Option<(u64, Pubkey, Vec<u8>)> {
+    account.map(|account| (account.lamports, account.owner, account.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::fixtures::account,
+        solana_sdk::{bpf_loader_upgradeable, system_program},
+    };
+
+    #[test]
+    fn test_missing_account_is_compared() {
+        assert!(is_compared(&None));
+    }
+
+    #[test]
+    fn test_program_owned_account_is_compared() {
+        assert!(is_compared(&Some(account(
+            1,
+            Pubkey::new_unique(),
+            &[1],
+            0
+        ))));
+        assert!(is_compared(&Some(account(1, system_program::id(), &[], 0))));
+    }
+
+    #[test]
+    fn test_executable_account_is_not_compared() {
+        let program = Account {
+            executable: true,
+            ..account(1, bpf_loader_upgradeable::id(), &[1], 0)
+        };
+        assert!(!is_compared(&Some(program)));
+    }
+
+    #[test]
+    fn test_sysvar_is_not_compared() {
+        assert!(!is_compared(&Some(account(1, sysvar::id(), &[1], 0))));
+    }
+
+    #[test]
+    fn test_account_state_ignores_rent_epoch() {
+        let first = account(1, Pubkey::new_unique(), &[1, 2, 3], 0);
+        let second = Account {
+            rent_epoch: 42,
+            ..first.clone()
+        };
+        assert_eq!(account_state(Some(first)), account_state(Some(second)));
+    }
+
+    #[test]
+    fn test_account_state_differs_on_lamports_owner_or_data() {
+        let first = account(1, Pubkey::new_unique(), &[1, 2, 3], 0);
+        let diverged = [
+            Account {
+                lamports: first.lamports + 1,
+                ..first.clone()
+            },
+            Account {
+                owner: Pubkey::new_unique(),
+                ..first.clone()
+            },
+            Account {
+                data: vec![3, 2, 1],
+                ..first.clone()
+            },
+        ];
+        for second in diverged {
+            assert_ne!(
+                account_state(Some(first.clone())),
+                account_state(Some(second))
+            );
+        }
+        assert_ne!(account_state(Some(first)), account_state(None));
+    }
+}
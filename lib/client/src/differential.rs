@@ -0,0 +1,286 @@
+use {
+    crate::{
+        compare::{account_state, is_compared},
+        error::BoomerangClientError,
+        interface::{BoomerangTestClient, ProcessedTransaction},
+        BoomerangClient,
+    },
+    solana_sdk::{
+        account::Account,
+        hash::Hash,
+        message::Message,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    std::collections::HashMap,
+};
+
+/// The keypairs of each of a message's signers, in the order it lists them,
+/// picked from the fee payer and the keypairs provided
+pub(crate) fn message_signers<'a>(
+    message: &Message,
+    fee_payer: &'a Keypair,
+    signers: &[&'a Keypair],
+) -> Result<Vec<&'a Keypair>, BoomerangClientError> {
+    message.account_keys[..message.header.num_required_signatures as usize]
+        .iter()
+        .map(|pubkey| {
+            std::iter::once(fee_payer)
+                .chain(signers.iter().copied())
+                .find(|keypair| keypair.pubkey() == *pubkey)
+                .ok_or(BoomerangClientError::MissingSigner(*pubkey))
+        })
+        .collect()
+}
+
+/// The lines of a transaction's logs recording each program invocation and
+/// how it ended.
+/// Messages logged by the programs themselves and the compute units they
+/// consumed are expected to differ between implementations, so are left out.
+fn invocation_logs(logs: &[String]) -> Vec<&str> {
+    logs.iter()
+        .map(String::as_str)
+        .filter(|log| {
+            !log.starts_with("Program log: ")
+                && !log.starts_with("Program data: ")
+                && !log.starts_with("Program return: ")
+        })
+        .filter(|log| {
+            log.contains(" invoke [") || log.ends_with(" success") || log.contains(" failed: ")
+        })
+        .collect()
+}
+
+/// A second implementation of the program under test, run in lockstep with
+/// the first
+pub(crate) struct DifferentialClient {
+    pub(crate) client: BoomerangClient,
+    program_files: (String, String),
+    /// Transactions already re-signed, so that processing a transaction again
+    /// processes the same transaction against both implementations
+    mirrored: HashMap<Signature, Transaction>,
+}
+impl DifferentialClient {
+    pub(crate) fn new(client: BoomerangClient, program_files: (String, String)) -> Self {
+        Self {
+            client,
+            program_files,
+            mirrored: HashMap::new(),
+        }
+    }
+
+    /// Re-sign a transaction for the second implementation with the fee payer
+    /// and the provided `signers`.
+    /// A transaction signed with the first implementation's last blockhash is
+    /// signed with the second's, while any other blockhash is taken as a
+    /// request for a new one.
+    /// A transaction processed before is mirrored as it was then.
+    pub(crate) async fn mirror_transaction(
+        &mut self,
+        transaction: &Transaction,
+        signers: &[&Keypair],
+        last_blockhash: &Hash,
+    ) -> Result<Transaction, BoomerangClientError> {
+        let signature = transaction.signatures[0];
+        if let Some(mirrored) = self.mirrored.get(&signature) {
+            return Ok(mirrored.clone());
+        }
+        let fee_payer = self.client.fee_payer();
+        let signers = message_signers(&transaction.message, &fee_payer, signers)?;
+
+        let recent_blockhash = if transaction.message.recent_blockhash == *last_blockhash {
+            self.client.last_blockhash()
+        } else {
+            self.client.new_latest_blockhash().await
+        };
+
+        let mut mirrored = Transaction::new_unsigned(transaction.message.clone());
+        mirrored.sign(&signers, recent_blockhash);
+        self.mirrored.insert(signature, mirrored.clone());
+        Ok(mirrored)
+    }
+
+    /// Process the re-signed transaction against the second implementation,
+    /// preflighting it if the first was, and panic if its result, invocation
+    /// logs, return data or the state of any account it references diverged
+    /// from the first implementation's
+    pub(crate) async fn process_and_compare(
+        &mut self,
+        transaction: Transaction,
+        processed: &ProcessedTransaction,
+        accounts: Vec<(Pubkey, Option<Account>)>,
+        preflight: bool,
+    ) -> Result<(), BoomerangClientError> {
+        let (first, second) = &self.program_files;
+        let mirrored = self
+            .client
+            .process_with_metadata(transaction, preflight)
+            .await?;
+
+        assert_eq!(
+            processed.result, mirrored.result,
+            "{} and {} diverged on the transaction's result\nLogs of {}: {:#?}\nLogs of {}: {:#?}",
+            first, second, first, processed.logs, second, mirrored.logs,
+        );
+        assert_eq!(
+            invocation_logs(&processed.logs),
+            invocation_logs(&mirrored.logs),
+            "{} and {} diverged on the programs invoked\nLogs of {}: {:#?}\nLogs of {}: {:#?}",
+            first,
+            second,
+            first,
+            processed.logs,
+            second,
+            mirrored.logs,
+        );
+        assert_eq!(
+            processed.return_data, mirrored.return_data,
+            "{} and {} diverged on the transaction's return data",
+            first, second,
+        );
+
+        for (pubkey, account) in accounts {
+            let mirrored_account = self.client.get_account(&pubkey).await?;
+            if is_compared(&account) && is_compared(&mirrored_account) {
+                assert_eq!(
+                    account_state(account),
+                    account_state(mirrored_account),
+                    "{} and {} diverged on the state of account {}",
+                    first,
+                    second,
+                    pubkey,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::instruction::{AccountMeta, Instruction},
+    };
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_invocation_logs_keep_invocations_and_outcomes() {
+        let logs = logs(&[
+            "Program 11111111111111111111111111111111 invoke [1]",
+            "Program 11111111111111111111111111111111 success",
+            "Program AddressLookupTab1e1111111111111111111111111 invoke [1]",
+            "Program AddressLookupTab1e1111111111111111111111111 failed: invalid instruction data",
+        ]);
+        assert_eq!(
+            invocation_logs(&logs),
+            logs.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_invocation_logs_drop_program_output_and_compute_units() {
+        let logs = logs(&[
+            "Program AddressLookupTab1e1111111111111111111111111 invoke [1]",
+            "Program log: Instruction: CreateLookupTable",
+            "Program data: AQID",
+            "Program return: AddressLookupTab1e1111111111111111111111111 AQID",
+            "Program AddressLookupTab1e1111111111111111111111111 consumed 1200 of 200000 \
+             compute units",
+            "Program AddressLookupTab1e1111111111111111111111111 success",
+        ]);
+        assert_eq!(
+            invocation_logs(&logs),
+            vec![
+                "Program AddressLookupTab1e1111111111111111111111111 invoke [1]",
+                "Program AddressLookupTab1e1111111111111111111111111 success",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invocation_logs_ignore_program_messages_that_look_like_invocations() {
+        let logs = logs(&[
+            "Program log: Program 11111111111111111111111111111111 invoke [2]",
+            "Program log: lookup table creation failed: already initialized",
+            "Program log: success",
+        ]);
+        assert!(invocation_logs(&logs).is_empty());
+    }
+
+    #[test]
+    fn test_invocation_logs_differ_when_implementations_invoke_differently() {
+        let first = logs(&[
+            "Program AddressLookupTab1e1111111111111111111111111 invoke [1]",
+            "Program 11111111111111111111111111111111 invoke [2]",
+            "Program 11111111111111111111111111111111 success",
+            "Program AddressLookupTab1e1111111111111111111111111 success",
+        ]);
+        let second = logs(&[
+            "Program AddressLookupTab1e1111111111111111111111111 invoke [1]",
+            "Program log: Skipping the transfer",
+            "Program AddressLookupTab1e1111111111111111111111111 success",
+        ]);
+        assert_ne!(invocation_logs(&first), invocation_logs(&second));
+    }
+
+    fn message(signers: &[&Keypair]) -> Message {
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new_readonly(signer.pubkey(), true))
+                .collect(),
+        );
+        Message::new(&[instruction], Some(&signers[0].pubkey()))
+    }
+
+    #[test]
+    fn test_message_signers_follow_the_message_order() {
+        let fee_payer = Keypair::new();
+        let first = Keypair::new();
+        let second = Keypair::new();
+        let message = message(&[&fee_payer, &first, &second]);
+
+        let signers = message_signers(&message, &fee_payer, &[&second, &first]).unwrap();
+        assert_eq!(
+            signers
+                .iter()
+                .map(|signer| signer.pubkey())
+                .collect::<Vec<_>>(),
+            message.account_keys[..3],
+        );
+    }
+
+    #[test]
+    fn test_message_signers_ignore_unneeded_keypairs() {
+        let fee_payer = Keypair::new();
+        let unneeded = Keypair::new();
+        let message = message(&[&fee_payer]);
+
+        let signers = message_signers(&message, &fee_payer, &[&unneeded]).unwrap();
+        assert_eq!(signers.len(), 1);
+    }
+
+    #[test]
+    fn test_message_signers_report_a_missing_keypair() {
+        let fee_payer = Keypair::new();
+        let missing = Keypair::new();
+        let message = message(&[&fee_payer, &missing]);
+
+        match message_signers(&message, &fee_payer, &[]) {
+            Err(BoomerangClientError::MissingSigner(pubkey)) => {
+                assert_eq!(pubkey, missing.pubkey())
+            }
+            Err(err) => panic!("Unexpected error: {}", err),
+            Ok(_) => panic!("Resolved a signer without its keypair"),
+        }
+    }
+}
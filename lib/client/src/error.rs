@@ -0,0 +1,100 @@
+use {
+    solana_client::client_error::ClientError,
+    solana_program_test::{BanksClientError, ProgramTestError},
+    solana_sdk::{clock::Slot, pubkey::Pubkey, transaction::TransactionError},
+    std::{fmt, time::Duration},
+};
+
+/// An error from a Boomerang test client
+#[derive(Debug)]
+pub enum BoomerangClientError {
+    /// The transaction was processed and failed
+    Transaction(TransactionError),
+    /// The request to the bank or RPC node failed before producing a result
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// The operation did not complete before the deadline
+    Timeout { operation: String, waited: Duration },
+    /// An account expected to exist was not found
+    AccountNotFound(Pubkey),
+    /// An account's data could not be deserialized as the expected type
+    InvalidAccountData(Pubkey),
+    /// The requested slot is not ahead of the current slot, and time can only
+    /// move forward
+    SlotNotInFuture { current: Slot, requested: Slot },
+    /// No keypair was provided for one of the transaction's signers, so it
+    /// can't be re-signed for the second implementation of a differential
+    /// test
+    MissingSigner(Pubkey),
+    /// The backend has no way to honour the request, e.g. setting the clock's
+    /// timestamp on a running test validator
+    Unsupported { operation: &'static str },
+}
+impl BoomerangClientError {
+    /// The transaction error, if the transaction was processed and failed
+    pub fn transaction_error(&self) -> Option<&TransactionError> {
+        match self {
+            Self::Transaction(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for BoomerangClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transaction(err) => write!(f, "Transaction failed: {}", err),
+            Self::Transport(err) => write!(f, "Transport error: {}", err),
+            Self::Timeout { operation, waited } => {
+                write!(f, "Timed out after {:?} {}", waited, operation)
+            }
+            Self::AccountNotFound(pubkey) => write!(f, "Account not found: {}", pubkey),
+            Self::InvalidAccountData(pubkey) => {
+                write!(f, "Failed to deserialize account data: {}", pubkey)
+            }
+            Self::SlotNotInFuture { current, requested } => write!(
+                f,
+                "Cannot warp to slot {}, the current slot is {}",
+                requested, current
+            ),
+            Self::MissingSigner(pubkey) => write!(
+                f,
+                "No keypair provided for the transaction's signer {}",
+                pubkey
+            ),
+            Self::Unsupported { operation } => {
+                write!(f, "`{}` is not supported by this backend", operation)
+            }
+        }
+    }
+}
+impl std::error::Error for BoomerangClientError {}
+
+impl From<TransactionError> for BoomerangClientError {
+    fn from(err: TransactionError) -> Self {
+        Self::Transaction(err)
+    }
+}
+
+impl From<BanksClientError> for BoomerangClientError {
+    fn from(err: BanksClientError) -> Self {
+        match err {
+            BanksClientError::TransactionError(err)
+            | BanksClientError::SimulationError { err, .. } => Self::Transaction(err),
+            err => Self::Transport(err.into()),
+        }
+    }
+}
+
+impl From<ClientError> for BoomerangClientError {
+    fn from(err: ClientError) -> Self {
+        match err.get_transaction_error() {
+            Some(err) => Self::Transaction(err),
+            None => Self::Transport(err.into()),
+        }
+    }
+}
+
+impl From<ProgramTestError> for BoomerangClientError {
+    fn from(err: ProgramTestError) -> Self {
+        Self::Transport(err.into())
+    }
+}
@@ -0,0 +1,136 @@
+use {
+    crate::error::BoomerangClientError,
+    serde::{Deserialize, Serialize},
+    solana_account_decoder::{UiAccount, UiAccountEncoding},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        clock::Epoch,
+        pubkey::Pubkey,
+    },
+    std::{
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+/// An account dump as written by `solana account --output json`, the same
+/// format `solana-test-validator` reads with `--account` and `--account-dir`
+#[derive(Deserialize, Serialize)]
+struct AccountFixture {
+    pubkey: String,
+    account: UiAccount,
+}
+
+/// Load an account from a JSON account dump, returning the address recorded
+/// in the dump alongside the account
+pub fn load_account_fixture(path: &Path) -> (Pubkey, Account) {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read account fixture {:?}: {}", path, err));
+    let fixture: AccountFixture = serde_json::from_str(&contents)
+        .unwrap_or_else(|err| panic!("Failed to parse account fixture {:?}: {}", path, err));
+    let pubkey = Pubkey::from_str(&fixture.pubkey)
+        .unwrap_or_else(|err| panic!("Invalid pubkey in account fixture {:?}: {}", path, err));
+    let account = fixture
+        .account
+        .decode::<Account>()
+        .unwrap_or_else(|| panic!("Failed to decode account data in fixture {:?}", path));
+    (pubkey, account)
+}
+
+/// Load every `.json` account dump in a directory, in file name order
+pub fn load_account_fixture_dir(directory: &Path) -> Vec<(Pubkey, Account)> {
+    let mut paths = std::fs::read_dir(directory)
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to read account fixture directory {:?}: {}",
+                directory, err
+            )
+        })
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| load_account_fixture(path))
+        .collect()
+}
+
+/// Write an account as a JSON account dump
+pub fn write_account_fixture(path: &Path, pubkey: &Pubkey, account: &AccountSharedData) {
+    let fixture = AccountFixture {
+        pubkey: pubkey.to_string(),
+        account: UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None),
+    };
+    let contents =
+        serde_json::to_string(&fixture).expect("Failed to serialize account fixture to JSON");
+    std::fs::write(path, contents)
+        .unwrap_or_else(|err| panic!("Failed to write account fixture {:?}: {}", path, err));
+}
+
+/// The file an account's dump is stored in within a fixture directory
+pub fn account_fixture_path(directory: &Path, pubkey: &Pubkey) -> PathBuf {
+    directory.join(format!("{}.json", pubkey))
+}
+
+/// A non-executable account with the given state, for building the accounts
+/// a test sets or expects
+pub fn account(lamports: u64, owner: Pubkey, data: &[u8], rent_epoch: Epoch) -> Account {
+    Account {
+        lamports,
+        data: data.to_vec(),
+        owner,
+        executable: false,
+        rent_epoch,
+    }
+}
+
+/// The address of an upgradeable program's executable data, if the account is
+/// an upgradeable program
+pub fn programdata_address(account: &Account) -> Option<Pubkey> {
+    if account.owner != bpf_loader_upgradeable::id() {
+        return None;
+    }
+    match account.deserialize_data::<UpgradeableLoaderState>() {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => Some(programdata_address),
+        _ => None,
+    }
+}
+
+/// Fetch accounts from the cluster at `rpc_url` and store each as a JSON
+/// account dump in `directory`, along with the executable data of any
+/// upgradeable programs among them.
+/// Returns the paths of the dumps written.
+pub async fn clone_account_fixtures(
+    rpc_url: &str,
+    addresses: &[Pubkey],
+    directory: &Path,
+) -> Result<Vec<PathBuf>, BoomerangClientError> {
+    let rpc_client = RpcClient::new(rpc_url.to_string());
+    std::fs::create_dir_all(directory).unwrap_or_else(|err| {
+        panic!(
+            "Failed to create fixture directory {:?}: {}",
+            directory, err
+        )
+    });
+
+    let mut addresses = addresses.to_vec();
+    let mut paths = Vec::with_capacity(addresses.len());
+    while let Some(address) = addresses.pop() {
+        let account = rpc_client.get_account(&address).await?;
+        if let Some(programdata_address) = programdata_address(&account) {
+            addresses.push(programdata_address);
+        }
+        let path = account_fixture_path(directory, &address);
+        write_account_fixture(&path, &address, &account.into());
+        paths.push(path);
+    }
+    Ok(paths)
+}
@@ -1,42 +1,143 @@
 use {
+    crate::{error::BoomerangClientError, recording::RecordingMode},
     async_trait::async_trait,
+    regex::Regex,
+    solana_boomerang_test_validator::start_options::BoomerangTestValidatorStartOptions,
     solana_sdk::{
-        account::{Account, ReadableAccount},
+        account::{Account, AccountSharedData, ReadableAccount},
+        clock::{Clock, UnixTimestamp},
         commitment_config::CommitmentConfig,
         hash::Hash,
         instruction::{Instruction, InstructionError},
+        native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
-        signature::{Keypair, Signature},
+        signature::{read_keypair_file, Keypair, Signature},
         signer::Signer,
         slot_history::Slot,
+        system_instruction,
         transaction::{Transaction, TransactionError},
+        transaction_context::TransactionReturnData,
     },
+    std::{path::PathBuf, time::Duration},
 };
 
 #[derive(Clone)]
 pub struct BoomerangTestClientConfig {
+    /// Directory of JSON account dumps to load at genesis
+    pub account_dir: Option<PathBuf>,
+    /// JSON account dumps to load at genesis, as `(address, dump_path)` pairs.
+    /// The address takes precedence over the one recorded in the dump.
+    pub accounts: Vec<(Pubkey, PathBuf)>,
+    /// Accounts cloned from a cluster with `boomerang fixtures clone` to load
+    /// at genesis from the workspace's local cache.
+    /// The test runner resolves these into `accounts`.
+    pub cloned_accounts: Vec<Pubkey>,
+    /// The setup config of a second implementation of the program to run
+    /// every transaction against in lockstep, failing on any divergence in
+    /// results, logs or account state. Both must share a program ID and fee
+    /// payer.
+    pub differential: Option<Box<BoomerangTestClientConfig>>,
+    /// Additional programs to load alongside the program under test, as
+    /// `(program_file, program_id)` pairs
+    pub extra_programs: Vec<(String, Pubkey)>,
     pub features_disabled: Vec<Pubkey>,
+    /// Keypair file to use as the fee payer, instead of a fresh keypair
+    pub payer_keypair: Option<PathBuf>,
+    /// Lamports to fund the fee payer with on startup
+    pub payer_lamports: u64,
     pub program_file: String,
     pub program_id: Pubkey,
+    /// Record each trial to, or replay it from, a directory of recordings
+    pub recording: Option<RecordingMode>,
     pub rpc_commitment: CommitmentConfig,
+    /// How long to wait for a sent transaction to reach `rpc_commitment`
+    pub rpc_confirmation_timeout: Duration,
     pub rpc_endpoint: String,
-    pub slots_per_epoch: u64,
+    /// Override the test validator's number of slots in an epoch, or keep its
+    /// default if `None`
+    pub slots_per_epoch: Option<u64>,
+    /// Accounts to set at genesis, for constructing state that can't be set
+    /// on a running test validator
+    pub staged_accounts: Vec<(Pubkey, AccountSharedData)>,
+    /// Extra options to start the test validator with, after those derived
+    /// from the rest of this config and the validator's pool slot. Options
+    /// the validator takes once, such as `RpcPort` or `WarpSlot`, replace the
+    /// derived ones, though ports are rejected when validators are pooled.
+    /// Program tests ignore these.
+    pub validator_start_options: Vec<BoomerangTestValidatorStartOptions>,
     pub warp_slot: Slot,
 }
 impl Default for BoomerangTestClientConfig {
     fn default() -> Self {
         Self {
+            account_dir: None,
+            accounts: vec![],
+            cloned_accounts: vec![],
+            differential: None,
+            extra_programs: vec![],
             features_disabled: vec![],
+            payer_keypair: None,
+            payer_lamports: LAMPORTS_PER_SOL,
             program_file: "program.so".to_string(),
             program_id: Pubkey::new_unique(),
+            recording: None,
             rpc_commitment: CommitmentConfig::processed(),
+            rpc_confirmation_timeout: Duration::from_secs(30),
             rpc_endpoint: "http://127.0.0.1:8899".to_string(),
-            slots_per_epoch: 300, // Arbitrarily small number for testing
+            slots_per_epoch: None,
+            staged_accounts: vec![],
+            validator_start_options: vec![],
             warp_slot: 0,
         }
     }
 }
 
+impl BoomerangTestClientConfig {
+    /// Load the fee payer from `payer_keypair`, or generate a fresh one
+    pub fn payer(&self) -> Keypair {
+        match &self.payer_keypair {
+            Some(path) => read_keypair_file(path)
+                .unwrap_or_else(|err| panic!("Failed to read payer keypair {:?}: {}", path, err)),
+            None => Keypair::new(),
+        }
+    }
+}
+
+/// The outcome of simulating a transaction
+#[derive(Debug)]
+pub struct SimulationResult {
+    /// The transaction's result, had it been processed
+    pub result: Result<(), TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+    pub return_data: Option<TransactionReturnData>,
+    /// The state of each requested account after the transaction, or `None`
+    /// if it would not exist
+    pub accounts: Vec<(Pubkey, Option<Account>)>,
+}
+
+/// An instruction invoked by a program while processing a transaction
+#[derive(Debug)]
+pub struct InnerInstruction {
+    /// The index of the transaction instruction that invoked it
+    pub index: u8,
+    pub instruction: Instruction,
+    pub stack_height: Option<u32>,
+}
+
+/// A transaction that was processed, successfully or not, along with the
+/// metadata recorded for it
+#[derive(Debug)]
+pub struct ProcessedTransaction {
+    pub result: Result<(), TransactionError>,
+    pub logs: Vec<String>,
+    /// The instructions invoked via CPI, or `None` if the backend doesn't
+    /// record them, as is the case for program tests
+    pub inner_instructions: Option<Vec<InnerInstruction>>,
+    pub return_data: Option<TransactionReturnData>,
+    pub units_consumed: Option<u64>,
+}
+
 /// A client for testing programs
 #[async_trait]
 pub trait BoomerangTestClient {
@@ -52,48 +153,131 @@ pub trait BoomerangTestClient {
     /// Get a new latest blockhash
     async fn new_latest_blockhash(&mut self) -> Hash;
 
-    /// Process a transaction
+    /// Process a transaction.
+    /// `signers` are the keypairs that signed it besides the fee payer, as
+    /// passed to `create_default_transaction`, so that it can be re-signed
+    /// while differential testing.
     async fn process_transaction(
         &mut self,
         transaction: Transaction,
-    ) -> Result<(), Option<TransactionError>>;
+        signers: &[&Keypair],
+    ) -> Result<(), BoomerangClientError>;
 
-    /// Confirm a transaction
-    async fn confirm_transaction(
-        &self,
-        signature: &Signature,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Create a new keypair funded with `lamports`, for use as an additional
+    /// signer
+    async fn create_funded_keypair(
+        &mut self,
+        lamports: u64,
+    ) -> Result<Keypair, BoomerangClientError>;
+
+    /// Process a transaction and fetch its metadata.
+    /// A failed transaction is returned as `Ok`, with the failure in its
+    /// `result`, so that its logs can still be inspected.
+    /// `signers` are as for `process_transaction`.
+    async fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+        signers: &[&Keypair],
+    ) -> Result<ProcessedTransaction, BoomerangClientError>;
+
+    /// Simulate a transaction without committing it, returning the state of
+    /// the provided accounts after it.
+    /// Program tests can't report post-transaction account state, so must be
+    /// passed no accounts.
+    async fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+        accounts: &[Pubkey],
+    ) -> Result<SimulationResult, BoomerangClientError>;
+
+    /// Wait for a transaction to be confirmed, returning its error if it
+    /// failed
+    async fn confirm_transaction(&self, signature: &Signature) -> Result<(), BoomerangClientError>;
 
     /// Get an account
     async fn get_account(
         &mut self,
         pubkey: &Pubkey,
-    ) -> Result<Option<Account>, Box<dyn std::error::Error>>;
+    ) -> Result<Option<Account>, BoomerangClientError>;
 
-    async fn poll_for_next_epoch(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Get an account's balance in lamports
+    async fn get_balance(&mut self, pubkey: &Pubkey) -> Result<u64, BoomerangClientError>;
+
+    /// Get the minimum balance for an account with `space` bytes of data to
+    /// be rent exempt
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        space: usize,
+    ) -> Result<u64, BoomerangClientError>;
 
-    async fn poll_slots(&self, num_slots: u64) -> Result<(), Box<dyn std::error::Error>>;
+    /// Overwrite an account's state.
+    /// Only program tests support this. A running test validator's accounts
+    /// can only change through transactions, and staging the account for the
+    /// validator's next start would only reach the tests of the next chunk,
+    /// so integration tests return `BoomerangClientError::Unsupported`
+    /// instead. Use the config's `staged_accounts` to set state for both
+    /// backends at genesis.
+    async fn set_account(
+        &mut self,
+        pubkey: &Pubkey,
+        account: &AccountSharedData,
+    ) -> Result<(), BoomerangClientError>;
+
+    /// Get all accounts owned by a program
+    async fn get_program_accounts(
+        &mut self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, BoomerangClientError>;
+
+    /// Wait until the cluster has entered the next epoch.
+    /// Program tests warp straight to the first slot of the next epoch.
+    async fn poll_for_next_epoch(&mut self) -> Result<(), BoomerangClientError>;
+
+    /// Wait until the cluster has advanced more than `num_slots` slots.
+    /// Program tests warp straight to the first slot past that point.
+    async fn poll_slots(&mut self, num_slots: u64) -> Result<(), BoomerangClientError>;
+
+    /// Get the clock of the bank transactions are currently processed against
+    async fn get_clock(&mut self) -> Result<Clock, BoomerangClientError>;
+
+    /// Move the cluster forward to `slot`.
+    /// Program tests warp the bank, while integration tests wait for the test
+    /// validator to reach the slot, timing out if it falls too far behind.
+    async fn warp_to_slot(&mut self, slot: Slot) -> Result<(), BoomerangClientError>;
+
+    /// Move the cluster forward to the first slot of the epoch `num_epochs`
+    /// epochs after the current one
+    async fn advance_epochs(&mut self, num_epochs: u64) -> Result<(), BoomerangClientError>;
+
+    /// Set the clock's `unix_timestamp`.
+    /// Only program tests support this, since a running test validator's
+    /// clock follows its own slot timing.
+    async fn set_unix_timestamp(
+        &mut self,
+        unix_timestamp: UnixTimestamp,
+    ) -> Result<(), BoomerangClientError>;
+
+    /// Point an instruction at the program under test.
+    ///
+    /// The program under test is loaded at the address it's declared with, so
+    /// instructions built for that address need no changes. This is for
+    /// instructions built for a different address, such as those from an
+    /// instruction builder for a native program whose implementation is being
+    /// tested at another address.
+    fn retarget_instruction(&self, mut instruction: Instruction) -> Instruction {
+        instruction.program_id = self.program_id();
+        instruction
+    }
 
     /// Create a transaction with the provided instructions, fee payer,
     /// signers, and recent blockhash
     fn create_transaction(
         &self,
-        instructions: &mut [Instruction],
+        instructions: &[Instruction],
         fee_payer: &Keypair,
         signers: &[&Keypair],
         recent_blockhash: Hash,
     ) -> Transaction {
-        // TODO: Hot-swapping the program ID at test runtime is necessary when
-        // testing a program that gets deployed during genesis on the test
-        // validator, such as a native program or an SPL Token program.
-        // The test validator startup options for adding programs at genesis
-        // will not overwrite the program account(s) at the existing address.
-        // In order to eliminate this hot-swapping, we have to figure out a way
-        // to load the program being tested at the proper address on test validator
-        // startup.
-        instructions.iter_mut().for_each(|ix| {
-            ix.program_id = self.program_id();
-        });
         Transaction::new_signed_with_payer(
             instructions,
             Some(&fee_payer.pubkey()),
@@ -105,20 +289,9 @@ pub trait BoomerangTestClient {
     /// Create a default transaction with the fee payer as the payer
     fn create_default_transaction(
         &self,
-        instructions: &mut [Instruction],
+        instructions: &[Instruction],
         additional_signers: &[&Keypair],
     ) -> Transaction {
-        // TODO: Hot-swapping the program ID at test runtime is necessary when
-        // testing a program that gets deployed during genesis on the test
-        // validator, such as a native program or an SPL Token program.
-        // The test validator startup options for adding programs at genesis
-        // will not overwrite the program account(s) at the existing address.
-        // In order to eliminate this hot-swapping, we have to figure out a way
-        // to load the program being tested at the proper address on test validator
-        // startup.
-        instructions.iter_mut().for_each(|ix| {
-            ix.program_id = self.program_id();
-        });
         let fee_payer = self.fee_payer();
         let recent_blockhash = self.last_blockhash();
         let mut signers = vec![&fee_payer];
@@ -129,20 +302,9 @@ pub trait BoomerangTestClient {
     /// Create a default transaction with a new latest blockhash
     async fn create_default_transaction_with_new_blockhash(
         &mut self,
-        instructions: &mut [Instruction],
+        instructions: &[Instruction],
         additional_signers: &[&Keypair],
     ) -> Transaction {
-        // TODO: Hot-swapping the program ID at test runtime is necessary when
-        // testing a program that gets deployed during genesis on the test
-        // validator, such as a native program or an SPL Token program.
-        // The test validator startup options for adding programs at genesis
-        // will not overwrite the program account(s) at the existing address.
-        // In order to eliminate this hot-swapping, we have to figure out a way
-        // to load the program being tested at the proper address on test validator
-        // startup.
-        instructions.iter_mut().for_each(|ix| {
-            ix.program_id = self.program_id();
-        });
         let fee_payer = self.fee_payer();
         let recent_blockhash = self.new_latest_blockhash().await;
         let mut signers = vec![&fee_payer];
@@ -150,12 +312,46 @@ pub trait BoomerangTestClient {
         self.create_transaction(instructions, &fee_payer, &signers, recent_blockhash)
     }
 
+    /// Create a new account owned by `owner` with `space` bytes of data,
+    /// funded by the fee payer.
+    /// Returns the new account's keypair.
+    async fn create_account(
+        &mut self,
+        owner: &Pubkey,
+        space: usize,
+        lamports: u64,
+    ) -> Result<Keypair, BoomerangClientError> {
+        let account = Keypair::new();
+        let instruction = system_instruction::create_account(
+            &self.fee_payer().pubkey(),
+            &account.pubkey(),
+            lamports,
+            space as u64,
+            owner,
+        );
+        let transaction = self
+            .create_default_transaction_with_new_blockhash(&[instruction], &[&account])
+            .await;
+        self.process_transaction(transaction, &[&account]).await?;
+        Ok(account)
+    }
+
+    /// Transfer lamports from the fee payer
+    async fn transfer(&mut self, to: &Pubkey, lamports: u64) -> Result<(), BoomerangClientError> {
+        let instruction = system_instruction::transfer(&self.fee_payer().pubkey(), to, lamports);
+        let transaction = self
+            .create_default_transaction_with_new_blockhash(&[instruction], &[])
+            .await;
+        self.process_transaction(transaction, &[]).await
+    }
+
     /// Helper to validate a transaction succeeded
     async fn expect_successful_transaction(
         &mut self,
         transaction: Transaction,
-    ) -> Result<(), Option<TransactionError>> {
-        self.process_transaction(transaction).await?;
+        signers: &[&Keypair],
+    ) -> Result<(), BoomerangClientError> {
+        self.process_transaction(transaction, signers).await?;
         Ok(())
     }
 
@@ -163,12 +359,14 @@ pub trait BoomerangTestClient {
     async fn expect_failed_transaction(
         &mut self,
         transaction: Transaction,
+        signers: &[&Keypair],
         expected_err: TransactionError,
     ) {
-        assert_eq!(
-            self.process_transaction(transaction).await.unwrap_err(),
-            Some(expected_err),
-        );
+        match self.process_transaction(transaction, signers).await {
+            Ok(()) => panic!("Transaction succeeded"),
+            Err(BoomerangClientError::Transaction(err)) => assert_eq!(err, expected_err),
+            Err(err) => panic!("Transaction could not be processed: {}", err),
+        }
     }
 
     /// Helper to validate a transaction failed with a specific
@@ -176,21 +374,84 @@ pub trait BoomerangTestClient {
     async fn expect_failed_transaction_instruction(
         &mut self,
         transaction: Transaction,
+        signers: &[&Keypair],
         index: u8,
         expected_err: InstructionError,
     ) {
-        let result = self.process_transaction(transaction).await;
+        let result = self.process_transaction(transaction, signers).await;
         match result {
             Ok(signature) => panic!("Transaction succeeded: {:#?}", signature),
-            Err(err) => {
-                if let Some(TransactionError::InstructionError(i, err)) = err {
-                    assert_eq!(i, index);
-                    assert_eq!(err, expected_err);
-                } else {
-                    panic!("Transaction failed with unknown error: {:#?}", err);
-                }
+            Err(BoomerangClientError::Transaction(TransactionError::InstructionError(i, err))) => {
+                assert_eq!(i, index);
+                assert_eq!(err, expected_err);
             }
+            Err(err) => panic!("Transaction failed with unknown error: {:#?}", err),
+        }
+    }
+
+    /// Helper to validate a transaction succeeds in simulation using fewer
+    /// than `limit` compute units.
+    /// Returns the compute units consumed.
+    async fn expect_compute_units_below(
+        &mut self,
+        transaction: Transaction,
+        limit: u64,
+    ) -> Result<u64, BoomerangClientError> {
+        let simulation = self.simulate_transaction(transaction, &[]).await?;
+        if let Err(err) = simulation.result {
+            panic!(
+                "Transaction failed in simulation: {}\nLogs: {:#?}",
+                err, simulation.logs
+            );
         }
+        assert!(
+            simulation.units_consumed < limit,
+            "Transaction consumed {} compute units, expected fewer than {}\nLogs: {:#?}",
+            simulation.units_consumed,
+            limit,
+            simulation.logs,
+        );
+        Ok(simulation.units_consumed)
+    }
+
+    /// Helper to validate a transaction logs a message containing `expected`
+    async fn expect_log_contains(
+        &mut self,
+        transaction: Transaction,
+        signers: &[&Keypair],
+        expected: &str,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        let processed = self
+            .process_transaction_with_metadata(transaction, signers)
+            .await?;
+        assert!(
+            processed.logs.iter().any(|log| log.contains(expected)),
+            "No log contains {:?}\nLogs: {:#?}",
+            expected,
+            processed.logs,
+        );
+        Ok(processed)
+    }
+
+    /// Helper to validate a transaction logs a message matching the regular
+    /// expression `pattern`
+    async fn expect_logs_match(
+        &mut self,
+        transaction: Transaction,
+        signers: &[&Keypair],
+        pattern: &str,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        let regex = Regex::new(pattern).expect("Invalid log pattern");
+        let processed = self
+            .process_transaction_with_metadata(transaction, signers)
+            .await?;
+        assert!(
+            processed.logs.iter().any(|log| regex.is_match(log)),
+            "No log matches {:?}\nLogs: {:#?}",
+            pattern,
+            processed.logs,
+        );
+        Ok(processed)
     }
 
     /// Helper to validate an account's state matches the provided value
@@ -198,13 +459,13 @@ pub trait BoomerangTestClient {
         &mut self,
         pubkey: &Pubkey,
         expected_state: &Account,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(account) = self.get_account(pubkey).await? {
-            assert_eq!(account, *expected_state);
-            Ok(())
-        } else {
-            panic!("Account not found: {}", pubkey);
-        }
+    ) -> Result<(), BoomerangClientError> {
+        let account = self
+            .get_account(pubkey)
+            .await?
+            .ok_or(BoomerangClientError::AccountNotFound(*pubkey))?;
+        assert_eq!(account, *expected_state);
+        Ok(())
     }
 
     /// Helper to validate an account's data matches the provided bytes
@@ -212,12 +473,12 @@ pub trait BoomerangTestClient {
         &mut self,
         pubkey: &Pubkey,
         expected_data: &[u8],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(account) = self.get_account(pubkey).await? {
-            assert_eq!(account.data(), expected_data);
-            Ok(())
-        } else {
-            panic!("Account not found: {}", pubkey);
-        }
+    ) -> Result<(), BoomerangClientError> {
+        let account = self
+            .get_account(pubkey)
+            .await?
+            .ok_or(BoomerangClientError::AccountNotFound(*pubkey))?;
+        assert_eq!(account.data(), expected_data);
+        Ok(())
     }
 }
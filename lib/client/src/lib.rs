@@ -1,18 +1,35 @@
 mod banks_client;
+mod compare;
+mod differential;
+mod error;
+pub mod fixtures;
 mod interface;
+mod poll;
+pub mod recording;
 mod rpc_client;
 
-pub use interface::{BoomerangTestClient, BoomerangTestClientConfig};
 use {
     async_trait::async_trait,
     banks_client::BoomerangBanksClient,
+    differential::{message_signers, DifferentialClient},
+    recording::{encode_signers, RecordedAccount, RecordedEvent, RecordedTransaction, Recording},
     rpc_client::BoomerangRpcClient,
     solana_sdk::{
-        account::Account,
+        account::{Account, AccountSharedData},
+        clock::{Clock, Slot, UnixTimestamp},
         hash::Hash,
         pubkey::Pubkey,
         signature::{Keypair, Signature},
-        transaction::{Transaction, TransactionError},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    std::sync::{Arc, Mutex},
+};
+pub use {
+    error::BoomerangClientError,
+    interface::{
+        BoomerangTestClient, BoomerangTestClientConfig, InnerInstruction, ProcessedTransaction,
+        SimulationResult,
     },
 };
 
@@ -20,9 +37,35 @@ pub struct BoomerangClient {
     pub banks: Option<BoomerangBanksClient>,
     pub rpc: Option<BoomerangRpcClient>,
     use_banks: bool,
+    differential: Option<Box<DifferentialClient>>,
+    recording: Option<Arc<Mutex<Recording>>>,
 }
 impl BoomerangClient {
     pub async fn new(config: &BoomerangTestClientConfig, use_banks: bool) -> Self {
+        let mut client = Self::setup(config, use_banks).await;
+        if let Some(differential_config) = &config.differential {
+            assert_eq!(
+                config.program_id, differential_config.program_id,
+                "Differential tests must load both implementations at the same program ID",
+            );
+            let differential_client = Self::setup(differential_config, use_banks).await;
+            assert_eq!(
+                client.fee_payer().pubkey(),
+                differential_client.fee_payer().pubkey(),
+                "Differential tests must use the same fee payer for both implementations",
+            );
+            client.differential = Some(Box::new(DifferentialClient::new(
+                differential_client,
+                (
+                    config.program_file.clone(),
+                    differential_config.program_file.clone(),
+                ),
+            )));
+        }
+        client
+    }
+
+    async fn setup(config: &BoomerangTestClientConfig, use_banks: bool) -> Self {
         let (banks, rpc) = if use_banks {
             let banks = BoomerangBanksClient::setup(config).await;
             (Some(banks), None)
@@ -34,6 +77,134 @@ impl BoomerangClient {
             banks,
             rpc,
             use_banks,
+            differential: None,
+            recording: None,
+        }
+    }
+
+    async fn fund_account(
+        &mut self,
+        pubkey: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), BoomerangClientError> {
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .fund_account(pubkey, lamports)
+                .await
+        } else {
+            self.rpc.as_ref().unwrap().airdrop(pubkey, lamports).await
+        }
+    }
+
+    /// Fetch the state of each of the provided accounts
+    async fn get_accounts(
+        &mut self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<(Pubkey, Option<Account>)>, BoomerangClientError> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            accounts.push((*pubkey, self.get_account(pubkey).await?));
+        }
+        Ok(accounts)
+    }
+
+    /// Process a transaction through the backend and fetch how it was
+    /// processed.
+    /// With `preflight`, an RPC backend preflights the transaction as
+    /// `process_transaction` does, so one that fails preflight never lands.
+    /// Without it, a transaction that fails while executing is sent on
+    /// without preflight, so that it lands and records its logs. Either way,
+    /// a transaction that fails preflight is returned with the failure in its
+    /// `result`, as a `BanksClient` returns one it rejects.
+    pub(crate) async fn process_with_metadata(
+        &mut self,
+        transaction: Transaction,
+        preflight: bool,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .process_transaction_with_metadata(transaction, &[])
+                .await
+        } else if preflight {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .process_transaction_and_fetch_metadata(transaction)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .process_transaction_with_metadata(transaction, &[])
+                .await
+        }
+    }
+
+    /// Process a transaction while differential testing or recording,
+    /// mirroring it to the second implementation and appending it to the
+    /// recording
+    async fn process_and_record(
+        &mut self,
+        transaction: Transaction,
+        signers: &[&Keypair],
+        preflight: bool,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        // A transaction is only recorded along with all of its signers, so
+        // that it can be re-signed on replay
+        let recorded_signers = match &self.recording {
+            Some(_) => {
+                let fee_payer = self.fee_payer();
+                encode_signers(&message_signers(&transaction.message, &fee_payer, signers)?)
+            }
+            None => vec![],
+        };
+        let last_blockhash = self.last_blockhash();
+        let mirrored = match self.differential.as_mut() {
+            Some(differential) => Some(
+                differential
+                    .mirror_transaction(&transaction, signers, &last_blockhash)
+                    .await?,
+            ),
+            None => None,
+        };
+        let message = transaction.message.clone();
+        let pre_accounts = self.get_accounts(&message.account_keys).await?;
+
+        let processed = self.process_with_metadata(transaction, preflight).await?;
+        let post_accounts = self.get_accounts(&message.account_keys).await?;
+
+        let recorded_accounts = |accounts: &[(Pubkey, Option<Account>)]| {
+            accounts
+                .iter()
+                .map(|(pubkey, account)| RecordedAccount::new(pubkey, account.as_ref()))
+                .collect()
+        };
+        self.record(RecordedEvent::Transaction(RecordedTransaction {
+            signers: recorded_signers,
+            pre_accounts: recorded_accounts(&pre_accounts),
+            post_accounts: recorded_accounts(&post_accounts),
+            message,
+            result: processed.result.clone(),
+        }));
+
+        if let Some(mirrored) = mirrored {
+            self.differential
+                .as_mut()
+                .unwrap()
+                .process_and_compare(mirrored, &processed, post_accounts, preflight)
+                .await?;
+        }
+        Ok(processed)
+    }
+
+    /// Append an event to the recording, if the client is recording
+    fn record(&self, event: RecordedEvent) {
+        if let Some(recording) = &self.recording {
+            recording.lock().unwrap().events.push(event);
         }
     }
 }
@@ -75,42 +246,175 @@ impl BoomerangTestClient for BoomerangClient {
     async fn process_transaction(
         &mut self,
         transaction: Transaction,
-    ) -> Result<(), Option<TransactionError>> {
+        signers: &[&Keypair],
+    ) -> Result<(), BoomerangClientError> {
+        if self.differential.is_some() || self.recording.is_some() {
+            return self
+                .process_and_record(transaction, signers, /* preflight */ true)
+                .await?
+                .result
+                .map_err(|err| err.into());
+        }
         if self.use_banks {
             self.banks
                 .as_mut()
                 .unwrap()
-                .process_transaction(transaction)
+                .process_transaction(transaction, signers)
                 .await
         } else {
             self.rpc
                 .as_mut()
                 .unwrap()
-                .process_transaction(transaction)
+                .process_transaction(transaction, signers)
                 .await
         }
     }
 
-    async fn poll_for_next_epoch(&self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn poll_for_next_epoch(&mut self) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential.client.poll_for_next_epoch().await?;
+        }
+        if self.use_banks {
+            self.banks.as_mut().unwrap().poll_for_next_epoch().await
+        } else {
+            self.rpc.as_mut().unwrap().poll_for_next_epoch().await
+        }?;
+        self.record(RecordedEvent::AdvanceEpochs { num_epochs: 1 });
+        Ok(())
+    }
+
+    async fn poll_slots(&mut self, num_slots: u64) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential.client.poll_slots(num_slots).await?;
+        }
+        if self.use_banks {
+            self.banks.as_mut().unwrap().poll_slots(num_slots).await
+        } else {
+            self.rpc.as_mut().unwrap().poll_slots(num_slots).await
+        }?;
+        self.record(RecordedEvent::PollSlots { num_slots });
+        Ok(())
+    }
+
+    async fn get_clock(&mut self) -> Result<Clock, BoomerangClientError> {
         if self.use_banks {
-            self.banks.as_ref().unwrap().poll_for_next_epoch().await
+            self.banks.as_mut().unwrap().get_clock().await
         } else {
-            self.rpc.as_ref().unwrap().poll_for_next_epoch().await
+            self.rpc.as_mut().unwrap().get_clock().await
         }
     }
 
-    async fn poll_slots(&self, num_slots: u64) -> Result<(), Box<dyn std::error::Error>> {
+    async fn warp_to_slot(&mut self, slot: Slot) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential.client.warp_to_slot(slot).await?;
+        }
         if self.use_banks {
-            self.banks.as_ref().unwrap().poll_slots(num_slots).await
+            self.banks.as_mut().unwrap().warp_to_slot(slot).await
         } else {
-            self.rpc.as_ref().unwrap().poll_slots(num_slots).await
+            self.rpc.as_mut().unwrap().warp_to_slot(slot).await
+        }?;
+        self.record(RecordedEvent::WarpToSlot { slot });
+        Ok(())
+    }
+
+    async fn advance_epochs(&mut self, num_epochs: u64) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential.client.advance_epochs(num_epochs).await?;
         }
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .advance_epochs(num_epochs)
+                .await
+        } else {
+            self.rpc.as_mut().unwrap().advance_epochs(num_epochs).await
+        }?;
+        self.record(RecordedEvent::AdvanceEpochs { num_epochs });
+        Ok(())
     }
 
-    async fn confirm_transaction(
-        &self,
-        signature: &Signature,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    async fn set_unix_timestamp(
+        &mut self,
+        unix_timestamp: UnixTimestamp,
+    ) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential
+                .client
+                .set_unix_timestamp(unix_timestamp)
+                .await?;
+        }
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .set_unix_timestamp(unix_timestamp)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .set_unix_timestamp(unix_timestamp)
+                .await
+        }?;
+        self.record(RecordedEvent::SetUnixTimestamp { unix_timestamp });
+        Ok(())
+    }
+
+    async fn create_funded_keypair(
+        &mut self,
+        lamports: u64,
+    ) -> Result<Keypair, BoomerangClientError> {
+        let keypair = Keypair::new();
+        self.fund_account(&keypair.pubkey(), lamports).await?;
+        if let Some(differential) = self.differential.as_mut() {
+            differential
+                .client
+                .fund_account(&keypair.pubkey(), lamports)
+                .await?;
+        }
+        self.record(RecordedEvent::FundAccount {
+            pubkey: keypair.pubkey().to_string(),
+            lamports,
+        });
+        Ok(keypair)
+    }
+
+    async fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+        signers: &[&Keypair],
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        if self.differential.is_none() && self.recording.is_none() {
+            return self
+                .process_with_metadata(transaction, /* preflight */ false)
+                .await;
+        }
+        self.process_and_record(transaction, signers, /* preflight */ false)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+        accounts: &[Pubkey],
+    ) -> Result<SimulationResult, BoomerangClientError> {
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .simulate_transaction(transaction, accounts)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .simulate_transaction(transaction, accounts)
+                .await
+        }
+    }
+
+    async fn confirm_transaction(&self, signature: &Signature) -> Result<(), BoomerangClientError> {
         if self.use_banks {
             self.banks
                 .as_ref()
@@ -129,11 +433,85 @@ impl BoomerangTestClient for BoomerangClient {
     async fn get_account(
         &mut self,
         pubkey: &Pubkey,
-    ) -> Result<Option<Account>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<Account>, BoomerangClientError> {
         if self.use_banks {
             self.banks.as_mut().unwrap().get_account(pubkey).await
         } else {
             self.rpc.as_mut().unwrap().get_account(pubkey).await
         }
     }
+
+    async fn get_balance(&mut self, pubkey: &Pubkey) -> Result<u64, BoomerangClientError> {
+        if self.use_banks {
+            self.banks.as_mut().unwrap().get_balance(pubkey).await
+        } else {
+            self.rpc.as_mut().unwrap().get_balance(pubkey).await
+        }
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        space: usize,
+    ) -> Result<u64, BoomerangClientError> {
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .get_minimum_balance_for_rent_exemption(space)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .get_minimum_balance_for_rent_exemption(space)
+                .await
+        }
+    }
+
+    async fn set_account(
+        &mut self,
+        pubkey: &Pubkey,
+        account: &AccountSharedData,
+    ) -> Result<(), BoomerangClientError> {
+        if let Some(differential) = self.differential.as_mut() {
+            differential.client.set_account(pubkey, account).await?;
+        }
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .set_account(pubkey, account)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .set_account(pubkey, account)
+                .await
+        }?;
+        self.record(RecordedEvent::SetAccount(RecordedAccount::new(
+            pubkey,
+            Some(&account.clone().into()),
+        )));
+        Ok(())
+    }
+
+    async fn get_program_accounts(
+        &mut self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, BoomerangClientError> {
+        if self.use_banks {
+            self.banks
+                .as_mut()
+                .unwrap()
+                .get_program_accounts(program_id)
+                .await
+        } else {
+            self.rpc
+                .as_mut()
+                .unwrap()
+                .get_program_accounts(program_id)
+                .await
+        }
+    }
 }
@@ -0,0 +1,114 @@
+use {
+    crate::error::BoomerangClientError,
+    std::{future::Future, time::Duration},
+    tokio::time::Instant,
+};
+
+const INITIAL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Poll `check` until it produces a value, doubling the interval between
+/// attempts up to a cap, and giving up once `timeout` has elapsed.
+/// `description` completes the sentence "Timed out after ..." in the error.
+pub(crate) async fn poll_with_backoff<T, F, Fut>(
+    description: &str,
+    timeout: Duration,
+    mut check: F,
+) -> Result<T, BoomerangClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>, BoomerangClientError>>,
+{
+    let start = Instant::now();
+    let mut interval = INITIAL_POLL_INTERVAL;
+    loop {
+        if let Some(value) = check().await? {
+            return Ok(value);
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Err(BoomerangClientError::Timeout {
+                operation: description.to_string(),
+                waited: elapsed,
+            });
+        }
+        tokio::time::sleep(interval.min(timeout - elapsed)).await;
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::cell::RefCell};
+
+    /// Poll a check that never produces a value, returning the times it was
+    /// called at, relative to the start, and the error it gave up with
+    async fn poll_until_timeout(timeout: Duration) -> (Vec<Duration>, BoomerangClientError) {
+        let start = Instant::now();
+        let calls = RefCell::new(vec![]);
+        let err = poll_with_backoff::<(), _, _>("polling", timeout, || {
+            calls.borrow_mut().push(start.elapsed());
+            async { Ok(None) }
+        })
+        .await
+        .unwrap_err();
+        (calls.into_inner(), err)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_returns_the_first_value() {
+        let mut calls = 0;
+        let value = poll_with_backoff("polling", Duration::from_secs(1), || {
+            calls += 1;
+            async move { Ok((calls == 3).then_some(calls)) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_interval_doubles_up_to_the_cap() {
+        let (calls, _) = poll_until_timeout(Duration::from_secs(10)).await;
+        let intervals = calls
+            .windows(2)
+            .map(|window| window[1] - window[0])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            intervals[..7],
+            [50, 100, 200, 400, 800, 1600, 2000].map(Duration::from_millis),
+        );
+        assert!(intervals
+            .iter()
+            .all(|interval| *interval <= MAX_POLL_INTERVAL));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_times_out_at_the_deadline() {
+        let timeout = Duration::from_secs(5);
+        let (calls, err) = poll_until_timeout(timeout).await;
+
+        // The last wait is cut short so the final check lands on the deadline
+        assert_eq!(calls.last(), Some(&timeout));
+        match err {
+            BoomerangClientError::Timeout { operation, waited } => {
+                assert_eq!(operation, "polling");
+                assert_eq!(waited, timeout);
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_stops_on_an_error() {
+        let mut calls = 0;
+        let err = poll_with_backoff::<(), _, _>("polling", Duration::from_secs(1), || {
+            calls += 1;
+            async { Err(BoomerangClientError::AccountNotFound(Default::default())) }
+        })
+        .await
+        .unwrap_err();
+        assert!(matches!(err, BoomerangClientError::AccountNotFound(_)));
+        assert_eq!(calls, 1);
+    }
+}
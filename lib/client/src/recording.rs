@@ -0,0 +1,362 @@
+use {
+    crate::{
+        compare::{account_state, is_compared},
+        error::BoomerangClientError,
+        interface::{BoomerangTestClient, BoomerangTestClientConfig},
+        BoomerangClient,
+    },
+    serde::{Deserialize, Serialize},
+    solana_account_decoder::{UiAccount, UiAccountEncoding},
+    solana_sdk::{
+        account::Account,
+        bs58,
+        clock::{Slot, UnixTimestamp},
+        hash::Hash,
+        message::Message,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    std::{
+        collections::HashMap,
+        future::Future,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex},
+    },
+};
+
+fn parse_pubkey(pubkey: &str) -> Pubkey {
+    Pubkey::from_str(pubkey)
+        .unwrap_or_else(|err| panic!("Invalid pubkey in recording {}: {}", pubkey, err))
+}
+
+fn parse_keypair(keypair: &str) -> Keypair {
+    bs58::decode(keypair)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| Keypair::from_bytes(&bytes).ok())
+        .unwrap_or_else(|| panic!("Invalid keypair in recording"))
+}
+
+/// Encode the keypairs of a transaction's signers for its recording
+pub(crate) fn encode_signers(signers: &[&Keypair]) -> Vec<String> {
+    signers
+        .iter()
+        .map(|signer| signer.to_base58_string())
+        .collect()
+}
+
+/// Whether a trial is recorded, or replayed from an earlier recording, and
+/// the directory its recording lives in
+#[derive(Clone, Debug)]
+pub enum RecordingMode {
+    /// Run the trial and record it
+    Record(PathBuf),
+    /// Replay the trial's recording in place of running the trial
+    Replay(PathBuf),
+}
+
+/// The file a trial is recorded to within a recording directory
+pub fn recording_path(directory: &Path, trial_name: &str) -> PathBuf {
+    let file_name = trial_name
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .replace("::", ".");
+    directory.join(format!("{}.json", file_name))
+}
+
+/// An account's state, or `None` if it didn't exist
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedAccount {
+    pub pubkey: String,
+    pub account: Option<UiAccount>,
+}
+impl RecordedAccount {
+    pub fn new(pubkey: &Pubkey, account: Option<&Account>) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            account: account.map(|account| {
+                UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None)
+            }),
+        }
+    }
+
+    pub fn decode(&self) -> (Pubkey, Option<Account>) {
+        let pubkey = parse_pubkey(&self.pubkey);
+        let account = self.account.as_ref().map(|account| {
+            account
+                .decode::<Account>()
+                .unwrap_or_else(|| panic!("Failed to decode recorded account {}", self.pubkey))
+        });
+        (pubkey, account)
+    }
+}
+
+/// A processed transaction, along with the state of every account it
+/// references before and after it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedTransaction {
+    /// The keypairs of the transaction's signers, in the order its message
+    /// lists them, as base58 strings.
+    /// Test keypairs are throwaway, so they're recorded in full for the
+    /// transaction to be re-signed with the same signers, and so the same
+    /// addresses, on replay.
+    pub signers: Vec<String>,
+    pub message: Message,
+    pub pre_accounts: Vec<RecordedAccount>,
+    pub post_accounts: Vec<RecordedAccount>,
+    pub result: Result<(), TransactionError>,
+}
+
+/// Something a trial did through its client that changed the cluster's state
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    FundAccount { pubkey: String, lamports: u64 },
+    Transaction(RecordedTransaction),
+    SetAccount(RecordedAccount),
+    WarpToSlot { slot: Slot },
+    AdvanceEpochs { num_epochs: u64 },
+    PollSlots { num_slots: u64 },
+    SetUnixTimestamp { unix_timestamp: UnixTimestamp },
+}
+
+/// Every state-changing call a trial made through its client, in order
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>,
+}
+impl Recording {
+    pub fn load(path: &Path) -> Self {
+        let contents = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Failed to read recording {:?}: {}", path, err));
+        serde_json::from_str(&contents)
+            .unwrap_or_else(|err| panic!("Failed to parse recording {:?}: {}", path, err))
+    }
+
+    pub fn save(&self, path: &Path) {
+        let contents =
+            serde_json::to_string_pretty(self).expect("Failed to serialize recording to JSON");
+        std::fs::write(path, contents)
+            .unwrap_or_else(|err| panic!("Failed to write recording {:?}: {}", path, err));
+    }
+}
+
+impl BoomerangClient {
+    /// Start recording every call that changes the cluster's state, beginning
+    /// with funding the fee payer with its current balance.
+    /// Returns the recording, which grows as the client is used.
+    pub async fn start_recording(&mut self) -> Arc<Mutex<Recording>> {
+        let fee_payer = self.fee_payer().pubkey();
+        let lamports = self
+            .get_balance(&fee_payer)
+            .await
+            .expect("Failed to get the fee payer's balance");
+        let recording = Arc::new(Mutex::new(Recording {
+            events: vec![RecordedEvent::FundAccount {
+                pubkey: fee_payer.to_string(),
+                lamports,
+            }],
+        }));
+        self.recording = Some(recording.clone());
+        recording
+    }
+
+    /// Replay a recording, re-signing each transaction with its recorded
+    /// signers against this client's blockhashes.
+    /// The recording's fee payer, rather than this client's, pays for every
+    /// transaction, funded by the recording's `FundAccount` events.
+    /// Panics if any transaction's result, or the state of any account it
+    /// references before or after it, diverges from the recording.
+    pub async fn replay(&mut self, recording: &Recording) -> Result<(), BoomerangClientError> {
+        // Transactions recorded with the same blockhash are replayed with the
+        // same blockhash, so duplicates are still rejected as duplicates
+        let mut blockhashes = HashMap::new();
+        for (index, event) in recording.events.iter().enumerate() {
+            match event {
+                RecordedEvent::FundAccount { pubkey, lamports } => {
+                    // A fee payer shared with this client is already funded,
+                    // so top it up to the recorded balance rather than adding
+                    // to it
+                    let pubkey = parse_pubkey(pubkey);
+                    let balance = self.get_balance(&pubkey).await?;
+                    if *lamports > balance {
+                        self.fund_account(&pubkey, lamports - balance).await?
+                    }
+                }
+                RecordedEvent::Transaction(recorded) => {
+                    self.replay_transaction(index, recorded, &mut blockhashes)
+                        .await?
+                }
+                RecordedEvent::SetAccount(recorded) => {
+                    let (pubkey, account) = recorded.decode();
+                    let account = account.expect("Recorded `set_account` has no account");
+                    self.set_account(&pubkey, &account.into()).await?
+                }
+                RecordedEvent::WarpToSlot { slot } => self.warp_to_slot(*slot).await?,
+                RecordedEvent::AdvanceEpochs { num_epochs } => {
+                    self.advance_epochs(*num_epochs).await?
+                }
+                RecordedEvent::PollSlots { num_slots } => self.poll_slots(*num_slots).await?,
+                RecordedEvent::SetUnixTimestamp { unix_timestamp } => {
+                    self.set_unix_timestamp(*unix_timestamp).await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn replay_transaction(
+        &mut self,
+        index: usize,
+        recorded: &RecordedTransaction,
+        blockhashes: &mut HashMap<Hash, Hash>,
+    ) -> Result<(), BoomerangClientError> {
+        let recent_blockhash = match blockhashes.get(&recorded.message.recent_blockhash) {
+            Some(blockhash) => *blockhash,
+            None => {
+                let blockhash = self.new_latest_blockhash().await;
+                blockhashes.insert(recorded.message.recent_blockhash, blockhash);
+                blockhash
+            }
+        };
+
+        let signers = recorded
+            .signers
+            .iter()
+            .map(|signer| parse_keypair(signer))
+            .collect::<Vec<_>>();
+        let signers = signers.iter().collect::<Vec<_>>();
+
+        self.expect_recorded_accounts(index, "before", &recorded.pre_accounts)
+            .await?;
+
+        let mut transaction = Transaction::new_unsigned(recorded.message.clone());
+        transaction.sign(&signers, recent_blockhash);
+        let processed = self
+            .process_transaction_with_metadata(transaction, &signers)
+            .await?;
+        assert_eq!(
+            processed.result, recorded.result,
+            "Replay diverged from the result recorded for event {}\nLogs: {:#?}",
+            index, processed.logs,
+        );
+
+        self.expect_recorded_accounts(index, "after", &recorded.post_accounts)
+            .await
+    }
+
+    async fn expect_recorded_accounts(
+        &mut self,
+        index: usize,
+        when: &str,
+        recorded_accounts: &[RecordedAccount],
+    ) -> Result<(), BoomerangClientError> {
+        for recorded in recorded_accounts {
+            let (pubkey, recorded_account) = recorded.decode();
+            let account = self.get_account(&pubkey).await?;
+            if is_compared(&account) && is_compared(&recorded_account) {
+                assert_eq!(
+                    account_state(account),
+                    account_state(recorded_account),
+                    "Replay diverged from the state of account {} recorded {} event {}",
+                    pubkey,
+                    when,
+                    index,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run a trial against a client set up from `config`.
+/// If the config's `recording` asks for it, the trial is recorded to, or its
+/// recording replayed from, the trial's file in the recording directory.
+pub async fn run_trial<F, Fut>(
+    config: &BoomerangTestClientConfig,
+    use_banks: bool,
+    trial_name: &str,
+    test_func: F,
+) where
+    F: FnOnce(BoomerangClient) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut client = BoomerangClient::new(config, use_banks).await;
+    match &config.recording {
+        Some(RecordingMode::Record(directory)) => {
+            let recording = client.start_recording().await;
+            test_func(client).await;
+            // Only trials that ran to completion are saved
+            recording
+                .lock()
+                .unwrap()
+                .save(&recording_path(directory, trial_name));
+        }
+        Some(RecordingMode::Replay(directory)) => {
+            let recording = Recording::load(&recording_path(directory, trial_name));
+            client
+                .replay(&recording)
+                .await
+                .unwrap_or_else(|err| panic!("Failed to replay {}: {}", trial_name, err));
+        }
+        None => test_func(client).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::instruction::{AccountMeta, Instruction},
+    };
+
+    #[test]
+    fn test_recorded_signers_round_trip() {
+        let signer = Keypair::new();
+        let encoded = encode_signers(&[&signer]);
+        assert_eq!(encoded.len(), 1);
+        assert_eq!(parse_keypair(&encoded[0]), signer);
+    }
+
+    #[test]
+    fn test_recorded_signers_re_sign_the_recorded_message() {
+        let fee_payer = Keypair::new();
+        let authority = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(authority.pubkey(), true)],
+        );
+        let message = Message::new(&[instruction], Some(&fee_payer.pubkey()));
+        let recorded: RecordedTransaction = serde_json::from_str(
+            &serde_json::to_string(&RecordedTransaction {
+                signers: encode_signers(&[&fee_payer, &authority]),
+                message,
+                pre_accounts: vec![],
+                post_accounts: vec![],
+                result: Ok(()),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        let signers = recorded
+            .signers
+            .iter()
+            .map(|signer| parse_keypair(signer))
+            .collect::<Vec<_>>();
+        let mut transaction = Transaction::new_unsigned(recorded.message);
+        transaction.sign(&signers.iter().collect::<Vec<_>>(), Hash::new_unique());
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid keypair in recording")]
+    fn test_recorded_signers_reject_a_pubkey() {
+        parse_keypair(&Pubkey::new_unique().to_string());
+    }
+}
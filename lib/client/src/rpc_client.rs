@@ -1,19 +1,137 @@
 use {
-    crate::interface::{BoomerangTestClient, BoomerangTestClientConfig},
+    crate::{
+        error::BoomerangClientError,
+        interface::{
+            BoomerangTestClient, BoomerangTestClientConfig, InnerInstruction, ProcessedTransaction,
+            SimulationResult,
+        },
+        poll::poll_with_backoff,
+    },
     async_trait::async_trait,
-    solana_client::nonblocking::rpc_client::RpcClient,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    solana_account_decoder::UiAccountEncoding,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::{
+            RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig,
+            RpcSimulateTransactionConfig, RpcTransactionConfig,
+        },
+        rpc_request::{RpcError, RpcResponseErrorData},
+    },
     solana_sdk::{
-        account::Account,
+        account::{from_account, Account, AccountSharedData},
+        bs58,
+        clock::{Clock, Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT},
         commitment_config::CommitmentConfig,
         hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::Message,
         pubkey::Pubkey,
         signature::{Keypair, Signature},
         signer::Signer,
+        sysvar,
         transaction::{Transaction, TransactionError},
+        transaction_context::TransactionReturnData,
     },
+    solana_transaction_status::{
+        UiInnerInstructions, UiInstruction, UiTransactionEncoding, UiTransactionReturnData,
+    },
+    std::{str::FromStr, time::Duration},
 };
 
+/// Extra time allowed for the test validator to reach a warp slot, on top of
+/// its expected slot timing
+const WARP_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
+
+fn decode_return_data(
+    return_data: UiTransactionReturnData,
+) -> Result<TransactionReturnData, BoomerangClientError> {
+    let program_id = Pubkey::from_str(&return_data.program_id)
+        .map_err(|err| BoomerangClientError::Transport(err.into()))?;
+    let data = BASE64_STANDARD
+        .decode(return_data.data.0)
+        .map_err(|err| BoomerangClientError::Transport(err.into()))?;
+    Ok(TransactionReturnData { program_id, data })
+}
+
+/// Resolve the compiled inner instructions reported over RPC against the
+/// transaction's account keys
+fn decode_inner_instructions(
+    message: &Message,
+    inner_instructions: Vec<UiInnerInstructions>,
+) -> Result<Vec<InnerInstruction>, BoomerangClientError> {
+    let mut decoded = vec![];
+    for inner in inner_instructions {
+        for instruction in inner.instructions {
+            let UiInstruction::Compiled(compiled) = instruction else {
+                continue;
+            };
+            let accounts = compiled
+                .accounts
+                .iter()
+                .map(|&i| {
+                    let i = i as usize;
+                    AccountMeta {
+                        pubkey: message.account_keys[i],
+                        is_signer: message.is_signer(i),
+                        is_writable: message.is_writable(i),
+                    }
+                })
+                .collect();
+            let data = bs58::decode(&compiled.data)
+                .into_vec()
+                .map_err(|err| BoomerangClientError::Transport(err.into()))?;
+            decoded.push(InnerInstruction {
+                index: inner.index,
+                instruction: Instruction {
+                    program_id: message.account_keys[compiled.program_id_index as usize],
+                    accounts,
+                    data,
+                },
+                stack_height: compiled.stack_height,
+            });
+        }
+    }
+    Ok(decoded)
+}
+
+/// Whether a transaction that failed with `err` was executed, so lands and
+/// is charged its fee, rather than being rejected before it was executed
+fn fails_executing(err: &TransactionError) -> bool {
+    matches!(
+        err,
+        TransactionError::InstructionError(..) | TransactionError::InsufficientFundsForRent { .. }
+    )
+}
+
+/// How a transaction that failed preflight was processed, as far as the
+/// simulation preflight ran it in shows, or the error if it failed before
+/// being simulated
+fn preflight_failure(err: ClientError) -> Result<ProcessedTransaction, BoomerangClientError> {
+    let simulation = match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+            ..
+        }) => Some(simulation),
+        _ => None,
+    };
+    match err.get_transaction_error() {
+        Some(transaction_err) => Ok(ProcessedTransaction {
+            result: Err(transaction_err),
+            logs: simulation
+                .and_then(|simulation| simulation.logs.clone())
+                .unwrap_or_default(),
+            inner_instructions: None,
+            return_data: None,
+            units_consumed: simulation.and_then(|simulation| simulation.units_consumed),
+        }),
+        None => Err(err.into()),
+    }
+}
+
 pub struct BoomerangRpcClient {
+    confirmation_timeout: Duration,
     fee_payer: Keypair,
     latest_blockhash: Hash,
     program_id: Pubkey,
@@ -22,27 +140,125 @@ pub struct BoomerangRpcClient {
 
 impl BoomerangRpcClient {
     pub async fn setup(config: &BoomerangTestClientConfig) -> Self {
-        let fee_payer = Keypair::new();
+        let fee_payer = config.payer();
         let program_id = config.program_id;
 
         let rpc_client =
             RpcClient::new_with_commitment(config.rpc_endpoint.clone(), config.rpc_commitment);
         let latest_blockhash = rpc_client.get_latest_blockhash().await.unwrap();
 
-        let signature = rpc_client
-            .request_airdrop(&fee_payer.pubkey(), 1000000000)
-            .await
-            .unwrap();
-        rpc_client.confirm_transaction(&signature).await.unwrap();
-
-        std::thread::sleep(std::time::Duration::from_secs(5));
-
-        Self {
+        let client = Self {
+            confirmation_timeout: config.rpc_confirmation_timeout,
             fee_payer,
             latest_blockhash,
             program_id,
             rpc_client,
+        };
+        if config.payer_lamports > 0 {
+            client
+                .airdrop(&client.fee_payer.pubkey(), config.payer_lamports)
+                .await
+                .unwrap();
         }
+        client
+    }
+
+    /// Airdrop lamports to an account and wait for the airdrop to land
+    pub(crate) async fn airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), BoomerangClientError> {
+        let signature = self.rpc_client.request_airdrop(pubkey, lamports).await?;
+        self.wait_for_signature(&signature).await
+    }
+
+    /// Wait for a sent transaction to reach the client's commitment, returning
+    /// its error if it landed but failed
+    async fn wait_for_signature(&self, signature: &Signature) -> Result<(), BoomerangClientError> {
+        let status = poll_with_backoff(
+            &format!("waiting for transaction {} to be confirmed", signature),
+            self.confirmation_timeout,
+            || async {
+                self.rpc_client
+                    .get_signature_status_with_commitment(signature, self.rpc_client.commitment())
+                    .await
+                    .map_err(|err| err.into())
+            },
+        )
+        .await?;
+        status.map_err(|err| err.into())
+    }
+
+    /// Process a transaction as `process_transaction` does, preflight
+    /// included, then fetch how it was processed.
+    /// A transaction that fails preflight never lands, so it's returned with
+    /// the failure and logs from preflight instead.
+    pub(crate) async fn process_transaction_and_fetch_metadata(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        match self.rpc_client.send_transaction(&transaction).await {
+            Ok(signature) => {
+                self.fetch_processed_transaction(&transaction, &signature)
+                    .await
+            }
+            Err(err) => preflight_failure(err),
+        }
+    }
+
+    /// Wait for a sent transaction to land, then fetch its result and
+    /// metadata
+    async fn fetch_processed_transaction(
+        &self,
+        transaction: &Transaction,
+        signature: &Signature,
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        if let Err(err) = self.wait_for_signature(signature).await {
+            if err.transaction_error().is_none() {
+                return Err(err);
+            }
+        }
+
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        // The transaction may not be retrievable at `confirmed` immediately
+        // after reaching a lower commitment
+        let confirmed = poll_with_backoff(
+            &format!("waiting for transaction {}", signature),
+            self.confirmation_timeout,
+            || async {
+                Ok(self
+                    .rpc_client
+                    .get_transaction_with_config(signature, config)
+                    .await
+                    .ok())
+            },
+        )
+        .await?;
+
+        let meta = confirmed.transaction.meta.ok_or_else(|| {
+            BoomerangClientError::Transport(
+                format!("No metadata recorded for transaction {}", signature).into(),
+            )
+        })?;
+        let inner_instructions = Option::<Vec<_>>::from(meta.inner_instructions)
+            .map(|inner| decode_inner_instructions(&transaction.message, inner))
+            .transpose()?;
+        let return_data = Option::from(meta.return_data)
+            .map(decode_return_data)
+            .transpose()?;
+
+        Ok(ProcessedTransaction {
+            result: meta.err.map_or(Ok(()), Err),
+            logs: Option::from(meta.log_messages).unwrap_or_default(),
+            inner_instructions,
+            return_data,
+            units_consumed: meta.compute_units_consumed.into(),
+        })
     }
 }
 
@@ -67,35 +283,104 @@ impl BoomerangTestClient for BoomerangRpcClient {
     async fn process_transaction(
         &mut self,
         transaction: Transaction,
-    ) -> Result<(), Option<TransactionError>> {
-        self.rpc_client
-            .send_transaction(&transaction)
-            .await
-            .map(|_| ())
-            .map_err(|err| err.get_transaction_error())
+        _signers: &[&Keypair],
+    ) -> Result<(), BoomerangClientError> {
+        // Preflight catches most failures, but the transaction can still fail
+        // once it lands, so wait to see how it was processed
+        let signature = self.rpc_client.send_transaction(&transaction).await?;
+        self.wait_for_signature(&signature).await
     }
 
-    async fn confirm_transaction(
-        &self,
-        signature: &Signature,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        loop {
-            if self
-                .rpc_client
-                .confirm_transaction_with_commitment(signature, CommitmentConfig::finalized())
-                .await?
-                .value
-            {
-                return Ok(());
+    async fn create_funded_keypair(
+        &mut self,
+        lamports: u64,
+    ) -> Result<Keypair, BoomerangClientError> {
+        let keypair = Keypair::new();
+        self.airdrop(&keypair.pubkey(), lamports).await?;
+        Ok(keypair)
+    }
+
+    async fn process_transaction_with_metadata(
+        &mut self,
+        transaction: Transaction,
+        _signers: &[&Keypair],
+    ) -> Result<ProcessedTransaction, BoomerangClientError> {
+        // Preflight first, since a transaction rejected before it's executed
+        // never lands, so would only be waited on until timing out
+        let signature = match self.rpc_client.send_transaction(&transaction).await {
+            Ok(signature) => signature,
+            Err(err) => {
+                let rejected = preflight_failure(err)?;
+                if !matches!(&rejected.result, Err(err) if fails_executing(err)) {
+                    return Ok(rejected);
+                }
+                // It failed while executing, so send it again without
+                // preflight for it to land and record its logs
+                self.rpc_client
+                    .send_transaction_with_config(
+                        &transaction,
+                        RpcSendTransactionConfig {
+                            skip_preflight: true,
+                            ..RpcSendTransactionConfig::default()
+                        },
+                    )
+                    .await?
             }
-            std::thread::sleep(std::time::Duration::from_secs(1));
-        }
+        };
+        self.fetch_processed_transaction(&transaction, &signature)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+        accounts: &[Pubkey],
+    ) -> Result<SimulationResult, BoomerangClientError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: true,
+            commitment: Some(self.rpc_client.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: accounts.iter().map(|pubkey| pubkey.to_string()).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let simulation = self
+            .rpc_client
+            .simulate_transaction_with_config(&transaction, config)
+            .await?
+            .value;
+
+        let return_data = simulation.return_data.map(decode_return_data).transpose()?;
+        let post_accounts = accounts
+            .iter()
+            .zip(simulation.accounts.unwrap_or_default())
+            .map(|(pubkey, ui_account)| match ui_account {
+                Some(ui_account) => ui_account
+                    .decode::<Account>()
+                    .map(|account| (*pubkey, Some(account)))
+                    .ok_or(BoomerangClientError::InvalidAccountData(*pubkey)),
+                None => Ok((*pubkey, None)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SimulationResult {
+            result: simulation.err.map_or(Ok(()), Err),
+            logs: simulation.logs.unwrap_or_default(),
+            units_consumed: simulation.units_consumed.unwrap_or_default(),
+            return_data,
+            accounts: post_accounts,
+        })
+    }
+
+    async fn confirm_transaction(&self, signature: &Signature) -> Result<(), BoomerangClientError> {
+        self.wait_for_signature(signature).await
     }
 
     async fn get_account(
         &mut self,
         pubkey: &Pubkey,
-    ) -> Result<Option<Account>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<Account>, BoomerangClientError> {
         self.rpc_client
             .get_account_with_commitment(pubkey, self.rpc_client.commitment())
             .await
@@ -103,26 +388,171 @@ impl BoomerangTestClient for BoomerangRpcClient {
             .map_err(|err| err.into())
     }
 
-    async fn poll_for_next_epoch(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let epoch_info = self.rpc_client.get_epoch_info().await?;
-        let current = epoch_info.epoch;
-        loop {
-            let epoch_info = self.rpc_client.get_epoch_info().await?;
-            if epoch_info.epoch > current {
-                return Ok(());
-            }
-            std::thread::sleep(std::time::Duration::from_secs(5));
+    async fn get_balance(&mut self, pubkey: &Pubkey) -> Result<u64, BoomerangClientError> {
+        self.rpc_client
+            .get_balance(pubkey)
+            .await
+            .map_err(|err| err.into())
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        space: usize,
+    ) -> Result<u64, BoomerangClientError> {
+        self.rpc_client
+            .get_minimum_balance_for_rent_exemption(space)
+            .await
+            .map_err(|err| err.into())
+    }
+
+    /// Accounts for a test validator are staged through the config's
+    /// `staged_accounts` before it starts, rather than here
+    async fn set_account(
+        &mut self,
+        _pubkey: &Pubkey,
+        _account: &AccountSharedData,
+    ) -> Result<(), BoomerangClientError> {
+        Err(BoomerangClientError::Unsupported {
+            operation: "set_account",
+        })
+    }
+
+    async fn get_program_accounts(
+        &mut self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, BoomerangClientError> {
+        self.rpc_client
+            .get_program_accounts(program_id)
+            .await
+            .map_err(|err| err.into())
+    }
+
+    async fn get_clock(&mut self) -> Result<Clock, BoomerangClientError> {
+        let account = self.rpc_client.get_account(&sysvar::clock::id()).await?;
+        from_account::<Clock, _>(&account)
+            .ok_or(BoomerangClientError::InvalidAccountData(sysvar::clock::id()))
+    }
+
+    async fn warp_to_slot(&mut self, slot: Slot) -> Result<(), BoomerangClientError> {
+        let start_slot = self.rpc_client.get_slot().await?;
+        if slot <= start_slot {
+            return Err(BoomerangClientError::SlotNotInFuture {
+                current: start_slot,
+                requested: slot,
+            });
         }
+
+        // A running validator can't be warped, so wait for it to get there,
+        // allowing it to run at half its target speed before giving up
+        let budget = Duration::from_millis((slot - start_slot) * DEFAULT_MS_PER_SLOT * 2)
+            + WARP_TIMEOUT_MARGIN;
+        poll_with_backoff(
+            &format!(
+                "waiting for slot {}, starting from slot {}",
+                slot, start_slot
+            ),
+            budget,
+            || async {
+                let current = self.rpc_client.get_slot().await?;
+                Ok((current >= slot).then_some(()))
+            },
+        )
+        .await
+    }
+
+    async fn advance_epochs(&mut self, num_epochs: u64) -> Result<(), BoomerangClientError> {
+        let epoch_info = self.rpc_client.get_epoch_info().await?;
+        let epoch_schedule = self.rpc_client.get_epoch_schedule().await?;
+        let slot = epoch_schedule.get_first_slot_in_epoch(epoch_info.epoch + num_epochs);
+        self.warp_to_slot(slot).await
     }
 
-    async fn poll_slots(&self, num_slots: u64) -> Result<(), Box<dyn std::error::Error>> {
+    async fn set_unix_timestamp(
+        &mut self,
+        _unix_timestamp: UnixTimestamp,
+    ) -> Result<(), BoomerangClientError> {
+        Err(BoomerangClientError::Unsupported {
+            operation: "set_unix_timestamp",
+        })
+    }
+
+    async fn poll_for_next_epoch(&mut self) -> Result<(), BoomerangClientError> {
+        self.advance_epochs(1).await
+    }
+
+    async fn poll_slots(&mut self, num_slots: u64) -> Result<(), BoomerangClientError> {
         let slot = self.rpc_client.get_slot().await?;
-        loop {
-            let current_slot = self.rpc_client.get_slot().await?;
-            if current_slot > slot + num_slots {
-                return Ok(());
-            }
-            std::thread::sleep(std::time::Duration::from_secs(1));
-        }
+        self.warp_to_slot(slot + num_slots + 1).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_client::rpc_response::RpcSimulateTransactionResult,
+        solana_sdk::instruction::InstructionError,
+    };
+
+    fn preflight_error(err: TransactionError, logs: &[&str]) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                RpcSimulateTransactionResult {
+                    err: Some(err),
+                    logs: Some(logs.iter().map(|log| log.to_string()).collect()),
+                    accounts: None,
+                    units_consumed: Some(1200),
+                    return_data: None,
+                },
+            ),
+        })
+        .into()
+    }
+
+    #[test]
+    fn test_preflight_failure_keeps_the_simulated_outcome() {
+        let err = TransactionError::InstructionError(0, InstructionError::InvalidArgument);
+        let processed = preflight_failure(preflight_error(
+            err.clone(),
+            &["Program 11111111111111111111111111111111 invoke [1]"],
+        ))
+        .unwrap();
+        assert_eq!(processed.result, Err(err));
+        assert_eq!(
+            processed.logs,
+            vec!["Program 11111111111111111111111111111111 invoke [1]"],
+        );
+        assert_eq!(processed.units_consumed, Some(1200));
+    }
+
+    #[test]
+    fn test_preflight_failure_returns_a_rejection_as_the_result() {
+        let processed =
+            preflight_failure(preflight_error(TransactionError::BlockhashNotFound, &[])).unwrap();
+        assert_eq!(processed.result, Err(TransactionError::BlockhashNotFound));
+    }
+
+    #[test]
+    fn test_preflight_failure_without_a_transaction_error_is_an_error() {
+        let err = ClientErrorKind::Custom("connection refused".to_string()).into();
+        assert!(matches!(
+            preflight_failure(err),
+            Err(BoomerangClientError::Transport(_)),
+        ));
+    }
+
+    #[test]
+    fn test_only_execution_failures_land() {
+        assert!(fails_executing(&TransactionError::InstructionError(
+            0,
+            InstructionError::AccountAlreadyInitialized,
+        )));
+        assert!(fails_executing(
+            &TransactionError::InsufficientFundsForRent { account_index: 1 }
+        ));
+        assert!(!fails_executing(&TransactionError::AlreadyProcessed));
+        assert!(!fails_executing(&TransactionError::BlockhashNotFound));
+        assert!(!fails_executing(&TransactionError::InsufficientFundsForFee));
     }
 }
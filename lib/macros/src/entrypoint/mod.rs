@@ -4,20 +4,33 @@ pub struct Entrypoint {
     programs: Vec<(String, String)>,
     program_tests: Vec<String>,
     integration_tests: Vec<String>,
-    migration_tests: Vec<(String, String)>,
+    migration_tests: Vec<(String, syn::Ident)>,
+    migration_test_mode: syn::Ident,
+    validator_pool_size: usize,
+    differential_tests: Vec<(String, String)>,
+    replay_tests: Vec<String>,
 }
 impl Entrypoint {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         programs: Vec<(String, String)>,
         program_tests: Vec<String>,
         integration_tests: Vec<String>,
-        migration_tests: Vec<(String, String)>,
+        migration_tests: Vec<(String, syn::Ident)>,
+        migration_test_mode: syn::Ident,
+        validator_pool_size: usize,
+        differential_tests: Vec<(String, String)>,
+        replay_tests: Vec<String>,
     ) -> Self {
         Self {
             programs,
             program_tests,
             integration_tests,
             migration_tests,
+            migration_test_mode,
+            validator_pool_size,
+            differential_tests,
+            replay_tests,
         }
     }
 }
@@ -42,6 +55,10 @@ impl From<&Entrypoint> for proc_macro2::TokenStream {
         let program_tests = &ast.program_tests;
         let integration_tests = &ast.integration_tests;
         let migration_tests = &ast.migration_tests;
+        let migration_test_mode = &ast.migration_test_mode;
+        let validator_pool_size = ast.validator_pool_size;
+        let differential_tests = &ast.differential_tests;
+        let replay_tests = &ast.replay_tests;
 
         let test_iterations = crate::iteration::Iteration::parse_iterations().unwrap();
 
@@ -74,9 +91,27 @@ impl From<&Entrypoint> for proc_macro2::TokenStream {
 
         let all_migration_tests_args_tokens = migration_tests
             .iter()
-            .map(|(name, file)| {
+            .map(|(name, target)| {
                 quote::quote! {
-                    (#name, #file)
+                    (#name, solana_boomerang::NativeProgram::#target)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let all_differential_tests_args_tokens = differential_tests
+            .iter()
+            .map(|(name, differential_name)| {
+                quote::quote! {
+                    (#name, #differential_name)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let all_replay_tests_args_tokens = replay_tests
+            .iter()
+            .map(|i| {
+                quote::quote! {
+                    #i
                 }
             })
             .collect::<Vec<_>>();
@@ -114,6 +149,14 @@ impl From<&Entrypoint> for proc_macro2::TokenStream {
                     #( #all_migration_tests_args_tokens ),*
                 ];
 
+                let differential_tests = &[
+                    #( #all_differential_tests_args_tokens ),*
+                ];
+
+                let replay_tests = &[
+                    #( #all_replay_tests_args_tokens ),*
+                ];
+
                 let tests: solana_boomerang::BoomerangTests = &[
                     #( #all_iterations_tokens ),*
                 ];
@@ -123,6 +166,10 @@ impl From<&Entrypoint> for proc_macro2::TokenStream {
                     program_tests,
                     integration_tests,
                     migration_tests,
+                    solana_boomerang::migration::MigrationTestMode::#migration_test_mode,
+                    #validator_pool_size,
+                    differential_tests,
+                    replay_tests,
                     tests,
                 ).await;
             }
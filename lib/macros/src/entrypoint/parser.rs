@@ -2,11 +2,18 @@ enum ParsedEntrypointArg {
     Programs(Vec<crate::parser::ParsedStringTupleItem>),
     ProgramTests(Vec<crate::parser::ParsedStringItem>),
     IntegrationTests(Vec<crate::parser::ParsedStringItem>),
-    MigrationTests(Vec<crate::parser::ParsedStringTupleItem>),
+    MigrationTests(Vec<crate::parser::ParsedStringPathTupleItem>),
+    MigrationTestMode(crate::parser::ParsedPathItem),
+    ValidatorPoolSize(crate::parser::ParsedIntItem),
+    DifferentialTests(Vec<crate::parser::ParsedStringTupleItem>),
+    ReplayTests(Vec<crate::parser::ParsedStringItem>),
 }
 impl syn::parse::Parse for ParsedEntrypointArg {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        use crate::parser::{parse_bracketed_list_arg, ParsedStringItem, ParsedStringTupleItem};
+        use crate::parser::{
+            parse_bracketed_list_arg, parse_singleton_arg, ParsedIntItem, ParsedPathItem,
+            ParsedStringItem, ParsedStringPathTupleItem, ParsedStringTupleItem,
+        };
 
         if input.peek(syn::Ident) {
             let ident = input.parse::<syn::Ident>()?;
@@ -21,8 +28,20 @@ impl syn::parse::Parse for ParsedEntrypointArg {
                     ParsedStringItem,
                 >(input)?)),
                 "migration_tests" => Ok(Self::MigrationTests(parse_bracketed_list_arg::<
+                    ParsedStringPathTupleItem,
+                >(input)?)),
+                "migration_test_mode" => Ok(Self::MigrationTestMode(parse_singleton_arg::<
+                    ParsedPathItem,
+                >(input)?)),
+                "validator_pool_size" => Ok(Self::ValidatorPoolSize(parse_singleton_arg::<
+                    ParsedIntItem,
+                >(input)?)),
+                "differential_tests" => Ok(Self::DifferentialTests(parse_bracketed_list_arg::<
                     ParsedStringTupleItem,
                 >(input)?)),
+                "replay_tests" => Ok(Self::ReplayTests(parse_bracketed_list_arg::<
+                    ParsedStringItem,
+                >(input)?)),
                 _ => Err(syn::Error::new(input.span(), "Unknown argument")),
             }
         } else {
@@ -31,13 +50,51 @@ impl syn::parse::Parse for ParsedEntrypointArg {
     }
 }
 
+/// Validate a migration target of the form `NativeProgram::<Program>`
+/// (optionally qualified, e.g. `solana_boomerang::NativeProgram::<Program>`)
+/// and return the `NativeProgram` variant it names.
+/// The variant is emitted with its original span, so rustc reports unknown
+/// native programs against the attribute.
+fn parse_migration_target(target: &syn::Path) -> syn::Result<syn::Ident> {
+    let segments = target.segments.iter().collect::<Vec<_>>();
+    match segments.as_slice() {
+        [.., native_program, variant] if native_program.ident == "NativeProgram" => {
+            Ok(variant.ident.clone())
+        }
+        _ => Err(syn::Error::new_spanned(
+            target,
+            "Migration target must be of the form `NativeProgram::<Program>`",
+        )),
+    }
+}
+
+/// Validate a migration test mode of the form `MigrationTestMode::<Mode>` and
+/// return the `MigrationTestMode` variant it names.
+/// As with migration targets, rustc reports unknown modes.
+fn parse_migration_test_mode(mode: &syn::Path) -> syn::Result<syn::Ident> {
+    let segments = mode.segments.iter().collect::<Vec<_>>();
+    match segments.as_slice() {
+        [.., enum_name, variant] if enum_name.ident == "MigrationTestMode" => {
+            Ok(variant.ident.clone())
+        }
+        _ => Err(syn::Error::new_spanned(
+            mode,
+            "Migration test mode must be of the form `MigrationTestMode::<Mode>`",
+        )),
+    }
+}
+
 pub fn parse_entrypoint(
     input: syn::parse::ParseStream,
 ) -> syn::Result<crate::entrypoint::Entrypoint> {
     let mut programs: Vec<(String, String)> = Vec::new();
     let mut program_tests: Vec<String> = Vec::new();
     let mut integration_tests: Vec<String> = Vec::new();
-    let mut migration_tests: Vec<(String, String)> = Vec::new();
+    let mut migration_tests: Vec<(String, syn::Ident)> = Vec::new();
+    let mut migration_test_mode = syn::Ident::new("AfterActivation", input.span());
+    let mut validator_pool_size: usize = 1;
+    let mut differential_tests: Vec<(String, String)> = Vec::new();
+    let mut replay_tests: Vec<String> = Vec::new();
 
     let args = crate::parser::parse_list::<ParsedEntrypointArg>(input)?;
 
@@ -59,8 +116,25 @@ pub fn parse_entrypoint(
                 });
             }
             ParsedEntrypointArg::MigrationTests(migration_tests_arg) => {
-                migration_tests_arg.iter().for_each(|arg| {
-                    migration_tests.push(arg.value());
+                for arg in migration_tests_arg {
+                    let (program, target) = arg.value();
+                    migration_tests.push((program, parse_migration_target(&target)?));
+                }
+            }
+            ParsedEntrypointArg::MigrationTestMode(migration_test_mode_arg) => {
+                migration_test_mode = parse_migration_test_mode(&migration_test_mode_arg.value())?;
+            }
+            ParsedEntrypointArg::ValidatorPoolSize(validator_pool_size_arg) => {
+                validator_pool_size = validator_pool_size_arg.value::<usize>();
+            }
+            ParsedEntrypointArg::DifferentialTests(differential_tests_arg) => {
+                differential_tests_arg.iter().for_each(|arg| {
+                    differential_tests.push(arg.value());
+                });
+            }
+            ParsedEntrypointArg::ReplayTests(replay_tests_arg) => {
+                replay_tests_arg.iter().for_each(|arg| {
+                    replay_tests.push(arg.value());
                 });
             }
         }
@@ -71,5 +145,9 @@ pub fn parse_entrypoint(
         program_tests,
         integration_tests,
         migration_tests,
+        migration_test_mode,
+        validator_pool_size,
+        differential_tests,
+        replay_tests,
     ))
 }
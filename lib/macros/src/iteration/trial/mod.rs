@@ -60,15 +60,30 @@ impl From<&Trial> for proc_macro2::TokenStream {
 
 #[derive(Default)]
 pub struct TrialConfig {
+    account_dir: Option<String>,
+    accounts: Vec<(String, String)>,
+    cloned_accounts: Vec<String>,
+    extra_programs: Vec<(String, String)>,
     features_disabled: Vec<syn::Path>,
+    payer_keypair: Option<String>,
+    payer_lamports: Option<u64>,
+    staged_accounts: Option<syn::Path>,
+    validator_start_options: Option<syn::Path>,
     warp_slot: u64,
 }
 
-
-
 impl PartialEq for TrialConfig {
     fn eq(&self, other: &Self) -> bool {
-        self.features_disabled == other.features_disabled && self.warp_slot == other.warp_slot
+        self.account_dir == other.account_dir
+            && self.accounts == other.accounts
+            && self.cloned_accounts == other.cloned_accounts
+            && self.extra_programs == other.extra_programs
+            && self.features_disabled == other.features_disabled
+            && self.payer_keypair == other.payer_keypair
+            && self.payer_lamports == other.payer_lamports
+            && self.staged_accounts == other.staged_accounts
+            && self.validator_start_options == other.validator_start_options
+            && self.warp_slot == other.warp_slot
     }
 }
 
@@ -86,14 +101,68 @@ impl quote::ToTokens for TrialConfig {
 
 impl From<&TrialConfig> for proc_macro2::TokenStream {
     fn from(ast: &TrialConfig) -> Self {
+        let account_dir = match &ast.account_dir {
+            Some(account_dir) => quote::quote! { Some(#account_dir.into()) },
+            None => quote::quote! { None },
+        };
+        let accounts = ast
+            .accounts
+            .iter()
+            .map(|(address, dump_path)| {
+                quote::quote! {
+                    (#address.parse().unwrap(), #dump_path.into())
+                }
+            })
+            .collect::<Vec<_>>();
+        let cloned_accounts = &ast.cloned_accounts;
+        let extra_programs = ast
+            .extra_programs
+            .iter()
+            .map(|(program_file, program_id)| {
+                quote::quote! {
+                    (#program_file.to_string(), #program_id.parse().unwrap())
+                }
+            })
+            .collect::<Vec<_>>();
         let features_disabled = &ast.features_disabled;
+        let payer_keypair = match &ast.payer_keypair {
+            Some(payer_keypair) => quote::quote! { Some(#payer_keypair.into()) },
+            None => quote::quote! { None },
+        };
+        // Left to the config's default when not provided
+        let payer_lamports = ast.payer_lamports.map(|payer_lamports| {
+            quote::quote! { payer_lamports: #payer_lamports, }
+        });
+        let staged_accounts = ast.staged_accounts.as_ref().map(|staged_accounts| {
+            quote::quote! { staged_accounts: #staged_accounts(), }
+        });
+        let validator_start_options =
+            ast.validator_start_options
+                .as_ref()
+                .map(|validator_start_options| {
+                    quote::quote! { validator_start_options: #validator_start_options(), }
+                });
         let warp_slot = ast.warp_slot;
 
         quote::quote! {
             solana_boomerang::client::BoomerangTestClientConfig {
+                account_dir: #account_dir,
+                accounts: vec![
+                    #( #accounts ),*
+                ],
+                cloned_accounts: vec![
+                    #( #cloned_accounts.parse().unwrap() ),*
+                ],
+                extra_programs: vec![
+                    #( #extra_programs ),*
+                ],
                 features_disabled: vec![
                     #( #features_disabled() ),*
                 ],
+                payer_keypair: #payer_keypair,
+                #payer_lamports
+                #staged_accounts
+                #validator_start_options
                 warp_slot: #warp_slot,
                 ..solana_boomerang::client::BoomerangTestClientConfig::default()
             }
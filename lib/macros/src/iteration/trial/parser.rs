@@ -1,5 +1,13 @@
 enum ParsedTrialConfigArg {
+    AccountDir(crate::parser::ParsedStringItem),
+    Accounts(Vec<crate::parser::ParsedStringTupleItem>),
+    ClonedAccounts(Vec<crate::parser::ParsedStringItem>),
+    ExtraPrograms(Vec<crate::parser::ParsedStringTupleItem>),
     DeactivateFeatures(Vec<crate::parser::ParsedPathItem>),
+    PayerKeypair(crate::parser::ParsedStringItem),
+    PayerLamports(crate::parser::ParsedIntItem),
+    StagedAccounts(crate::parser::ParsedPathItem),
+    ValidatorStartOptions(crate::parser::ParsedPathItem),
     WarpSlot(crate::parser::ParsedIntItem),
 }
 impl syn::parse::Parse for ParsedTrialConfigArg {
@@ -7,11 +15,41 @@ impl syn::parse::Parse for ParsedTrialConfigArg {
         if input.peek(syn::Ident) {
             let ident = input.parse::<syn::Ident>()?;
             match ident.to_string().as_str() {
+                "account_dir" => Ok(Self::AccountDir(crate::parser::parse_singleton_arg::<
+                    crate::parser::ParsedStringItem,
+                >(input)?)),
+                "accounts" => Ok(Self::Accounts(crate::parser::parse_bracketed_list_arg::<
+                    crate::parser::ParsedStringTupleItem,
+                >(input)?)),
+                "cloned_accounts" => Ok(Self::ClonedAccounts(
+                    crate::parser::parse_bracketed_list_arg::<crate::parser::ParsedStringItem>(
+                        input,
+                    )?,
+                )),
+                "extra_programs" => Ok(Self::ExtraPrograms(
+                    crate::parser::parse_bracketed_list_arg::<crate::parser::ParsedStringTupleItem>(
+                        input,
+                    )?,
+                )),
                 "features_disabled" => Ok(Self::DeactivateFeatures(
                     crate::parser::parse_bracketed_list_arg::<crate::parser::ParsedPathItem>(
                         input,
                     )?,
                 )),
+                "payer_keypair" => Ok(Self::PayerKeypair(crate::parser::parse_singleton_arg::<
+                    crate::parser::ParsedStringItem,
+                >(input)?)),
+                "payer_lamports" => Ok(Self::PayerLamports(crate::parser::parse_singleton_arg::<
+                    crate::parser::ParsedIntItem,
+                >(input)?)),
+                "staged_accounts" => {
+                    Ok(Self::StagedAccounts(crate::parser::parse_singleton_arg::<
+                        crate::parser::ParsedPathItem,
+                    >(input)?))
+                }
+                "validator_start_options" => Ok(Self::ValidatorStartOptions(
+                    crate::parser::parse_singleton_arg::<crate::parser::ParsedPathItem>(input)?,
+                )),
                 "warp_slot" => Ok(Self::WarpSlot(crate::parser::parse_singleton_arg::<
                     crate::parser::ParsedIntItem,
                 >(input)?)),
@@ -26,18 +64,56 @@ impl syn::parse::Parse for ParsedTrialConfigArg {
 pub fn parse_trial_config(
     input: syn::parse::ParseStream,
 ) -> syn::Result<crate::iteration::trial::TrialConfig> {
+    let mut account_dir: Option<String> = None;
+    let mut accounts: Vec<(String, String)> = Vec::new();
+    let mut cloned_accounts: Vec<String> = Vec::new();
+    let mut extra_programs: Vec<(String, String)> = Vec::new();
     let mut features_disabled: Vec<syn::Path> = Vec::new();
+    let mut payer_keypair: Option<String> = None;
+    let mut payer_lamports: Option<u64> = None;
+    let mut staged_accounts: Option<syn::Path> = None;
+    let mut validator_start_options: Option<syn::Path> = None;
     let mut warp_slot: u64 = 0;
 
     let args = crate::parser::parse_list::<ParsedTrialConfigArg>(input)?;
 
     for arg in args {
         match arg {
+            ParsedTrialConfigArg::AccountDir(account_dir_arg) => {
+                account_dir = Some(account_dir_arg.value());
+            }
+            ParsedTrialConfigArg::Accounts(accounts_arg) => {
+                accounts_arg.iter().for_each(|arg| {
+                    accounts.push(arg.value());
+                });
+            }
+            ParsedTrialConfigArg::ClonedAccounts(cloned_accounts_arg) => {
+                cloned_accounts_arg.iter().for_each(|arg| {
+                    cloned_accounts.push(arg.value());
+                });
+            }
+            ParsedTrialConfigArg::ExtraPrograms(extra_programs_arg) => {
+                extra_programs_arg.iter().for_each(|arg| {
+                    extra_programs.push(arg.value());
+                });
+            }
             ParsedTrialConfigArg::DeactivateFeatures(features_disabled_arg) => {
                 features_disabled_arg.iter().for_each(|arg| {
                     features_disabled.push(arg.value());
                 });
             }
+            ParsedTrialConfigArg::PayerKeypair(payer_keypair_arg) => {
+                payer_keypair = Some(payer_keypair_arg.value());
+            }
+            ParsedTrialConfigArg::PayerLamports(payer_lamports_arg) => {
+                payer_lamports = Some(payer_lamports_arg.value::<u64>());
+            }
+            ParsedTrialConfigArg::StagedAccounts(staged_accounts_arg) => {
+                staged_accounts = Some(staged_accounts_arg.value());
+            }
+            ParsedTrialConfigArg::ValidatorStartOptions(validator_start_options_arg) => {
+                validator_start_options = Some(validator_start_options_arg.value());
+            }
             ParsedTrialConfigArg::WarpSlot(warp_slot_arg) => {
                 warp_slot = warp_slot_arg.value::<u64>();
             }
@@ -45,7 +121,15 @@ pub fn parse_trial_config(
     }
 
     Ok(crate::iteration::trial::TrialConfig {
+        account_dir,
+        accounts,
+        cloned_accounts,
+        extra_programs,
         features_disabled,
+        payer_keypair,
+        payer_lamports,
+        staged_accounts,
+        validator_start_options,
         warp_slot,
     })
 }
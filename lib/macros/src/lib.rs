@@ -16,7 +16,28 @@ mod parser;
 ///   local test validator.
 /// * `migration_tests` is a list of tuples of the form `(source_program_name,
 ///   target)` that declares which native program the declared source program
-///   should be migration tested against.
+///   should be migration tested against. The target is a `NativeProgram`
+///   variant, such as `NativeProgram::AddressLookupTable`.
+/// * `migration_test_mode` is how migration tests exercise the target program.
+///   `MigrationTestMode::AfterActivation` (the default) only runs the tests
+///   after the migration, while `MigrationTestMode::BeforeAndAfter` runs them
+///   against the native program first and compares the two sets of results.
+/// * `validator_pool_size` is the number of test validators to run integration
+///   and replay test chunks on in parallel. Each validator gets its own ports
+///   and ledger. Defaults to `1`, which runs chunks serially on the default
+///   ports.
+/// * `differential_tests` is a list of tuples of the form `(program_name,
+///   differential_program_name)` that declares pairs of implementations to run
+///   in lockstep. Each trial's transactions are processed by both, each in its
+///   own bank, and the trial fails if their results, invocation logs, return
+///   data or the state of any account the transaction references diverge. Both
+///   implementations must be declared at the same program ID, and the trial
+///   must create its transactions with the client's helpers so they can be
+///   re-signed for the second implementation.
+/// * `replay_tests` is a list of program names whose trials are recorded as
+///   program tests, then replayed against a test validator. Each replayed
+///   transaction must produce the same result and account state as it did in
+///   the recording. Recordings are written to `tmp/recordings/<program_name>`.
 ///
 /// Example:
 ///
@@ -43,13 +64,15 @@ mod parser;
 ///     migration_tests = [
 ///         (
 ///             "solana_address_lookup_table_rust",
-///             "NativeProgram::AddressLookupTable"
+///             NativeProgram::AddressLookupTable
 ///         ),
 ///         (
 ///             "solana_address_lookup_table_zig",
-///             "NativeProgram::AddressLookupTable"
+///             NativeProgram::AddressLookupTable
 ///         ),
 ///     ],
+///     migration_test_mode = MigrationTestMode::BeforeAndAfter,
+///     validator_pool_size = 4,
 /// )]
 /// async fn main() {}
 /// ```
@@ -65,9 +88,33 @@ pub fn main(attr: proc_macro::TokenStream, _: proc_macro::TokenStream) -> proc_m
 /// The attribute accepts arguments for configuring the test case's startup
 /// behavior. These startup configs are valid for both a `BanksClient` program
 /// test and an `RpcClient` integration/migration test.
+/// * `accounts` is a list of `(address, dump_path)` tuples of JSON account
+///   dumps, as written by `solana account --output json`, to load at genesis.
+/// * `account_dir` is a directory of JSON account dumps to load at genesis.
+/// * `cloned_accounts` is a list of addresses of accounts cloned from a cluster
+///   with `boomerang fixtures clone`, to load at genesis from the workspace's
+///   `fixtures/cloned-accounts` cache without network access.
+/// * `extra_programs` is a list of `(program_file, program_id)` tuples for
+///   additional programs to load alongside the program under test, such as
+///   programs it invokes via CPI.
 /// * `features_disabled` is a list of feature IDs from the Solana SDK's
 ///   `feature_set` to disable on startup. validator before running the test
 ///   case.
+/// * `payer_keypair` is the path to a keypair file to use as the fee payer,
+///   instead of a fresh keypair.
+/// * `payer_lamports` is the number of lamports to fund the fee payer with.
+///   Defaults to 1 SOL.
+/// * `staged_accounts` is the path to a function returning a `Vec<(Pubkey,
+///   AccountSharedData)>` of accounts to set at genesis, for constructing
+///   pre-states such as a deactivated lookup table. The path is resolved from
+///   the test crate's root, e.g. `crate::fixtures::accounts`.
+/// * `validator_start_options` is the path to a function returning a
+///   `Vec<BoomerangTestValidatorStartOptions>` of extra options to start the
+///   test validator with, such as a compute unit limit or ticks per slot, to
+///   mirror a cluster's conditions. Options the validator takes once, such as
+///   an RPC port, replace those Boomerang derives, though ports can only be set
+///   with a `validator_pool_size` of 1. These can be put together with
+///   `BoomerangTestValidatorStartOptionsBuilder`. Program tests ignore them.
 /// * `warp_slot` is the slot to warp the bank or test validator to before
 ///   running the test case.
 ///
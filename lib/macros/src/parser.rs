@@ -55,6 +55,30 @@ impl syn::parse::Parse for ParsedStringTupleItem {
     }
 }
 
+/// A tuple of a string and a path, where the path may also be written as a
+/// string literal for backwards compatibility, e.g. `("name", Enum::Variant)`
+/// or `("name", "Enum::Variant")`
+pub struct ParsedStringPathTupleItem(syn::LitStr, syn::Path);
+impl ParsedStringPathTupleItem {
+    pub fn value(&self) -> (String, syn::Path) {
+        (self.0.value(), self.1.to_owned())
+    }
+}
+impl syn::parse::Parse for ParsedStringPathTupleItem {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+        let val1: syn::LitStr = content.parse()?;
+        content.parse::<syn::Token![,]>()?;
+        let val2: syn::Path = if content.peek(syn::LitStr) {
+            content.parse::<syn::LitStr>()?.parse()?
+        } else {
+            content.parse()?
+        };
+        Ok(Self(val1, val2))
+    }
+}
+
 pub fn parse_singleton_arg<T: syn::parse::Parse>(input: syn::parse::ParseStream) -> syn::Result<T> {
     let _equals_sign = input.parse::<syn::Token![=]>()?;
     input.parse::<T>()
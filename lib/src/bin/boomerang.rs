@@ -0,0 +1,52 @@
+use {solana_boomerang::fixtures, solana_sdk::pubkey::Pubkey, std::str::FromStr};
+
+const USAGE: &str = "\
+Usage: boomerang fixtures clone [--url <URL_OR_MONIKER>] <ADDRESS>...
+
+Clone accounts from a cluster into the workspace's local cache, for trials to
+load with `#[boomerang::test(cloned_accounts = [...])]`.
+
+Options:
+  -u, --url <URL_OR_MONIKER>  The cluster to clone from: a URL, or one of
+                              mainnet-beta, devnet, testnet or localhost
+                              [default: mainnet-beta]";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+#[tokio::main]
+async fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["fixtures", "clone", ref args @ ..] => args.to_vec(),
+        ["-h" | "--help"] => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => usage_error("Unknown command"),
+    };
+
+    let mut url = "mainnet-beta";
+    let mut addresses = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg {
+            "-u" | "--url" => {
+                url = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("`--url` requires a value"));
+            }
+            address => addresses.push(
+                Pubkey::from_str(address)
+                    .unwrap_or_else(|_| usage_error(&format!("Invalid address `{}`", address))),
+            ),
+        }
+    }
+    if addresses.is_empty() {
+        usage_error("No addresses to clone");
+    }
+
+    fixtures::clone_accounts(url, &addresses).await;
+}
@@ -0,0 +1,119 @@
+use {
+    crate::{
+        dirs, output,
+        program::{BoomerangProgramTestChunk, BoomerangProgramTestIteration},
+        BoomerangTests,
+    },
+    libtest_mimic::Arguments,
+    solana_sdk::signature::Keypair,
+    std::path::PathBuf,
+};
+
+/// A differential test iteration for a pair of implementations of a program.
+/// Every trial runs against both implementations in lockstep, each in its own
+/// bank, and fails as soon as their outcomes diverge.
+pub struct BoomerangDifferentialTestIteration {
+    chunks: Vec<BoomerangProgramTestChunk>,
+    differential_program_file: String,
+    payer_path: PathBuf,
+    program_file: String,
+}
+impl BoomerangDifferentialTestIteration {
+    pub fn new(
+        program: &(&str, &str),
+        differential_program: &(&str, &str),
+        tests: BoomerangTests<'_>,
+    ) -> Self {
+        let (program_file, program_id) = program;
+        let (differential_program_file, differential_program_id) = differential_program;
+        assert_eq!(
+            program_id, differential_program_id,
+            "Differential tests must load both implementations at the same program ID, but {} is \
+             declared at {} and {} at {}",
+            program_file, program_id, differential_program_file, differential_program_id,
+        );
+
+        // Transactions are re-signed for the second implementation, so both
+        // must be paid for by the same fee payer
+        let payer_path = dirs::differential_payer_path(program_file, differential_program_file);
+
+        let chunks = BoomerangProgramTestIteration::new(program, tests, /* use_banks */ true)
+            .chunks()
+            .into_iter()
+            .map(|mut chunk| {
+                let config = chunk.config_mut();
+                if config.payer_keypair.is_none() {
+                    config.payer_keypair = Some(payer_path.clone());
+                }
+                let mut differential_config = config.clone();
+                differential_config.program_file = differential_program_file.to_string();
+                config.differential = Some(Box::new(differential_config));
+                chunk
+            })
+            .collect();
+
+        Self {
+            chunks,
+            differential_program_file: differential_program_file.to_string(),
+            payer_path,
+            program_file: program_file.to_string(),
+        }
+    }
+
+    /// Run the tests for the entire iteration in parallel.
+    pub fn parallel_run(self) {
+        dirs::create_directory(&self.payer_path.parent().unwrap().to_path_buf());
+        dirs::write_keypair_to_path(&Keypair::new(), &self.payer_path);
+
+        let args = Arguments::default();
+        let trials = self
+            .chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.trials())
+            .collect();
+        libtest_mimic::run(&args, trials).exit_if_failed();
+    }
+}
+
+/// The Differential Test runner.
+/// Runs each declared pair of implementations against each other as program
+/// tests, since both banks then start from the same genesis and clock.
+pub struct BoomerangDifferentialTest {
+    iterations: Vec<BoomerangDifferentialTestIteration>,
+}
+impl BoomerangDifferentialTest {
+    pub fn new(
+        programs: &[(&str, &str)],
+        differential_tests: &[(&str, &str)],
+        tests: BoomerangTests<'_>,
+    ) -> Self {
+        let find_program = |program_file: &str| {
+            *programs
+                .iter()
+                .find(|(name, _)| *name == program_file)
+                .unwrap()
+        };
+        Self {
+            iterations: differential_tests
+                .iter()
+                .map(|(program_file, differential_program_file)| {
+                    BoomerangDifferentialTestIteration::new(
+                        &find_program(program_file),
+                        &find_program(differential_program_file),
+                        tests,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn run(self) {
+        for iteration in self.iterations {
+            output::starting_differential_tests(
+                &iteration.program_file,
+                &iteration.differential_program_file,
+            );
+            iteration.parallel_run();
+        }
+    }
+}
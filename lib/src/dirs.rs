@@ -1,4 +1,8 @@
-use {cargo_metadata::MetadataCommand, solana_sdk::signature::Keypair, std::path::PathBuf};
+use {
+    cargo_metadata::MetadataCommand,
+    solana_sdk::signature::Keypair,
+    std::path::{Path, PathBuf},
+};
 
 pub fn workspace_root() -> PathBuf {
     MetadataCommand::new()
@@ -18,6 +22,12 @@ pub fn solana_install_path() -> PathBuf {
     workspace_root().join(".solana")
 }
 
+/// The file pinning the revision of the Solana fork migration tests build,
+/// kept in the workspace so every checkout builds the same fork
+pub fn solana_revision_lock_path() -> PathBuf {
+    workspace_root().join("solana-fork.lock")
+}
+
 pub fn solana_cli_path() -> PathBuf {
     solana_install_path()
         .join("target")
@@ -44,14 +54,50 @@ pub fn solana_test_validator_path_string() -> String {
         .to_string()
 }
 
+/// The local cache of accounts cloned with `boomerang fixtures clone`, kept
+/// in the workspace so tests can load them offline
+pub fn cloned_accounts_path() -> PathBuf {
+    workspace_root().join("fixtures").join("cloned-accounts")
+}
+
 pub fn temporary_directory_path() -> PathBuf {
     workspace_root().join("tmp")
 }
 
+/// The directory staged accounts are written to as JSON account dumps for the
+/// test validator with the given ledger to load at genesis
+pub fn staged_accounts_path(ledger_path: &Path) -> PathBuf {
+    temporary_directory_path()
+        .join("staged-accounts")
+        .join(ledger_path.file_name().unwrap())
+}
+
+/// The keypair file both implementations in a differential test use as their
+/// fee payer
+pub fn differential_payer_path(program_file: &str, differential_program_file: &str) -> PathBuf {
+    temporary_directory_path()
+        .join("differential-payers")
+        .join(format!(
+            "{}-{}.json",
+            program_file, differential_program_file
+        ))
+}
+
+/// The directory a program's trials are recorded to for replay
+pub fn recording_directory_path(program_file: &str) -> PathBuf {
+    temporary_directory_path()
+        .join("recordings")
+        .join(program_file)
+}
+
 pub fn test_ledger_path() -> PathBuf {
     workspace_root().join("test-ledger")
 }
 
+pub fn pooled_test_ledger_path(slot: usize) -> PathBuf {
+    workspace_root().join(format!("test-ledger-{}", slot))
+}
+
 pub fn create_directory(path: &PathBuf) {
     if !path.exists() {
         std::fs::create_dir_all(path).expect("Failed to create directory");
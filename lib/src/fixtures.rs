@@ -0,0 +1,59 @@
+use {
+    crate::{dirs, output},
+    solana_boomerang_client::{
+        fixtures::{
+            account_fixture_path, clone_account_fixtures, load_account_fixture, programdata_address,
+        },
+        BoomerangTestClientConfig,
+    },
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// Resolve a cluster moniker, as accepted by the Solana CLI's `--url`, to its
+/// RPC URL. Anything else is taken to be a URL already.
+fn rpc_url(url_or_moniker: &str) -> String {
+    match url_or_moniker {
+        "m" | "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+        "d" | "devnet" => "https://api.devnet.solana.com",
+        "t" | "testnet" => "https://api.testnet.solana.com",
+        "l" | "localhost" => "http://localhost:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+/// Clone accounts from a cluster into the workspace's local cache, for trials
+/// to load with `cloned_accounts`.
+pub async fn clone_accounts(url_or_moniker: &str, addresses: &[Pubkey]) {
+    let url = rpc_url(url_or_moniker);
+    let directory = dirs::cloned_accounts_path();
+    let paths = clone_account_fixtures(&url, addresses, &directory)
+        .await
+        .unwrap_or_else(|err| panic!("Failed to clone accounts from {}: {}", url, err));
+    output::cloned_account_fixtures(&url, &directory, &paths);
+}
+
+/// Point the config's `accounts` at the cached dumps of its `cloned_accounts`,
+/// including the executable data of any upgradeable programs among them.
+pub(crate) fn resolve_cloned_accounts(config: &mut BoomerangTestClientConfig) {
+    let directory = dirs::cloned_accounts_path();
+    let mut addresses = config.cloned_accounts.clone();
+    while let Some(address) = addresses.pop() {
+        if config.accounts.iter().any(|(pubkey, _)| *pubkey == address) {
+            continue;
+        }
+        let path = account_fixture_path(&directory, &address);
+        if !path.exists() {
+            panic!(
+                "Cloned account {} is not in the local cache at {:?}. Clone it with `cargo run \
+                 -p solana-boomerang --bin boomerang -- fixtures clone {}`",
+                address, directory, address,
+            );
+        }
+        let (_, account) = load_account_fixture(&path);
+        if let Some(programdata_address) = programdata_address(&account) {
+            addresses.push(programdata_address);
+        }
+        config.accounts.push((address, path));
+    }
+}
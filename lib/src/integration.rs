@@ -1,17 +1,27 @@
 use {
     crate::{
-        dirs, output,
-        program::{BoomerangProgramTest, BoomerangProgramTestIteration},
+        output,
+        pool::{assert_no_port_options, ValidatorPoolSlot, MAX_POOL_SIZE},
+        program::{BoomerangProgramTest, BoomerangProgramTestChunk, BoomerangProgramTestIteration},
         validator_options::IntoTestValidatorStartOptions,
         BoomerangTests,
     },
+    solana_boomerang_client::BoomerangTestClientConfig,
     solana_boomerang_test_validator::BoomerangTestValidator,
+    std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+    },
 };
 
 pub struct BoomerangIntegrationTest {
     iterations: Vec<BoomerangProgramTestIteration>,
     solana_cli_alias: String,
     solana_test_validator_alias: String,
+    validator_pool_size: usize,
 }
 impl Default for BoomerangIntegrationTest {
     fn default() -> Self {
@@ -19,42 +29,119 @@ impl Default for BoomerangIntegrationTest {
             iterations: Vec::new(),
             solana_cli_alias: "solana".to_string(),
             solana_test_validator_alias: "solana-test-validator".to_string(),
+            validator_pool_size: 1,
         }
     }
 }
 
 impl BoomerangIntegrationTest {
-    pub fn new(programs: &[(&str, &str)], tests: BoomerangTests<'_>) -> Self {
+    /// Create the integration test runner.
+    /// With a `validator_pool_size` greater than one, chunks are run in
+    /// parallel, each against its own test validator with isolated ports and
+    /// ledger, so no chunk's `validator_start_options` may set a port.
+    pub fn new(
+        programs: &[(&str, &str)],
+        tests: BoomerangTests<'_>,
+        validator_pool_size: usize,
+    ) -> Self {
+        assert!(
+            validator_pool_size <= MAX_POOL_SIZE,
+            "`validator_pool_size` must be at most {}",
+            MAX_POOL_SIZE
+        );
+        if validator_pool_size > 1 {
+            tests
+                .iter()
+                .for_each(|(config, _)| assert_no_port_options(&config.validator_start_options));
+        }
         Self {
             iterations: BoomerangProgramTest::build_program_test_iterations(
                 programs, tests, /* use_banks */ false,
             ),
+            validator_pool_size: validator_pool_size.max(1),
             ..Self::default()
         }
     }
 
-    pub fn run(self) {
-        for (i, iteration) in self.iterations.into_iter().enumerate() {
+    /// Apply `configure` to the setup config of every chunk, along with the
+    /// name of the program the chunk tests
+    pub fn configure(&mut self, configure: impl Fn(&str, &mut BoomerangTestClientConfig)) {
+        self.iterations.iter_mut().for_each(|iteration| {
+            let program_file = iteration.program_file().to_string();
+            iteration.configure(|config| configure(&program_file, config));
+        });
+    }
+
+    /// Start a fresh test validator in the given pool slot, point the chunk's
+    /// config at it, and run the chunk's trials.
+    /// Returns whether any of the trials failed.
+    fn run_chunk(&self, slot: &ValidatorPoolSlot, mut chunk: BoomerangProgramTestChunk) -> bool {
+        // The chunk's own options go last, so they override the slot's ports
+        let mut test_validator = BoomerangTestValidator::new(
+            slot.ledger_path(),
+            &self.solana_cli_alias,
+            &self.solana_test_validator_alias,
+            &[
+                &chunk.config().to_test_validator_start_options(),
+                &slot.stage_accounts(chunk.config()),
+                &slot.to_test_validator_start_options(),
+                &chunk.config().validator_start_options,
+            ],
+        );
+        chunk.config_mut().rpc_endpoint = test_validator.rpc_url().to_string();
+        test_validator.solana_test_validator_teardown();
+        test_validator.solana_test_validator_start();
+
+        let conclusion = chunk.run_without_exit();
+
+        test_validator.solana_test_validator_teardown();
+        slot.remove_staged_accounts();
+
+        conclusion.has_failed()
+    }
+
+    pub fn run(mut self) {
+        let iterations = std::mem::take(&mut self.iterations);
+        for iteration in iterations {
             output::starting_integration_tests(iteration.program_file());
 
             let chunks = iteration.chunks();
             let num_chunks = chunks.len();
 
-            for chunk in chunks {
-                output::chunk(i + 1, num_chunks);
-
-                let test_validator = BoomerangTestValidator::new(
-                    dirs::test_ledger_path(),
-                    &self.solana_cli_alias,
-                    &self.solana_test_validator_alias,
-                    &[&chunk.config().to_test_validator_start_options()],
-                );
-                test_validator.solana_test_validator_teardown();
-                test_validator.solana_test_validator_start();
-
-                chunk.run();
+            if self.validator_pool_size == 1 {
+                let slot = ValidatorPoolSlot::serial();
+                for (j, chunk) in chunks.into_iter().enumerate() {
+                    output::chunk(j + 1, num_chunks);
+                    if self.run_chunk(&slot, chunk) {
+                        std::process::exit(101);
+                    }
+                }
+                continue;
+            }
 
-                test_validator.solana_test_validator_teardown();
+            // Each worker owns one pool slot and pulls chunks off the shared
+            // queue until it is empty
+            let queue = Mutex::new(chunks.into_iter().enumerate().collect::<VecDeque<_>>());
+            let failed = AtomicBool::new(false);
+            std::thread::scope(|scope| {
+                for slot in 0..self.validator_pool_size.min(num_chunks) {
+                    let (this, queue, failed) = (&self, &queue, &failed);
+                    scope.spawn(move || {
+                        let slot = ValidatorPoolSlot::pooled(slot);
+                        loop {
+                            let Some((j, chunk)) = queue.lock().unwrap().pop_front() else {
+                                break;
+                            };
+                            output::chunk(j + 1, num_chunks);
+                            if this.run_chunk(&slot, chunk) {
+                                failed.store(true, Ordering::Relaxed);
+                            }
+                        }
+                    });
+                }
+            });
+            if failed.load(Ordering::Relaxed) {
+                std::process::exit(101);
             }
         }
     }
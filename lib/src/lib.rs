@@ -1,17 +1,27 @@
+pub mod differential;
 mod dirs;
+pub mod fixtures;
 pub mod integration;
 pub mod migration;
+mod native_program;
 mod output;
+mod pool;
 pub mod program;
+pub mod replay;
 pub mod validator_options;
 
 use {
-    client::BoomerangTestClientConfig, integration::BoomerangIntegrationTest, libtest_mimic::Trial,
-    migration::BoomerangMigrationTest, program::BoomerangProgramTest,
+    client::BoomerangTestClientConfig,
+    differential::BoomerangDifferentialTest,
+    integration::BoomerangIntegrationTest,
+    libtest_mimic::Trial,
+    migration::{BoomerangMigrationTest, MigrationTestMode},
+    program::BoomerangProgramTest,
+    replay::BoomerangReplayTest,
 };
 pub use {
-    libtest_mimic, solana_boomerang_client as client, solana_boomerang_macros as boomerang,
-    solana_boomerang_test_validator as test_validator, tokio,
+    libtest_mimic, native_program::NativeProgram, solana_boomerang_client as client,
+    solana_boomerang_macros as boomerang, solana_boomerang_test_validator as test_validator, tokio,
 };
 
 fn select_test_programs<'a>(
@@ -34,12 +44,12 @@ macro_rules! boomerang_trial {
                     .enable_all()
                     .build()
                     .unwrap()
-                    .block_on(async {
-                        let client =
-                            solana_boomerang::client::BoomerangClient::new(&config, use_banks)
-                                .await;
-                        $test_func(client).await
-                    });
+                    .block_on(solana_boomerang::client::recording::run_trial(
+                        &config,
+                        use_banks,
+                        stringify!($test_func),
+                        $test_func,
+                    ));
                 Ok(())
             })
         }
@@ -50,14 +60,24 @@ pub type BoomerangTestFn = fn(BoomerangTestClientConfig, bool) -> Trial;
 pub type BoomerangTest<'a> = (BoomerangTestClientConfig, &'a [BoomerangTestFn]);
 pub type BoomerangTests<'a> = &'a [BoomerangTest<'a>];
 
+#[allow(clippy::too_many_arguments)]
 pub async fn entrypoint(
     programs: &[(&str, &str)],
     program_tests: &[&str],
     integration_tests: &[&str],
-    migration_tests: &[(&str, &str)],
+    migration_tests: &[(&str, NativeProgram)],
+    migration_test_mode: MigrationTestMode,
+    validator_pool_size: usize,
+    differential_tests: &[(&str, &str)],
+    replay_tests: &[&str],
     tests: BoomerangTests<'_>,
 ) {
-    if program_tests.is_empty() && integration_tests.is_empty() && migration_tests.is_empty() {
+    if program_tests.is_empty()
+        && integration_tests.is_empty()
+        && migration_tests.is_empty()
+        && differential_tests.is_empty()
+        && replay_tests.is_empty()
+    {
         output::no_tests_to_run();
         return;
     }
@@ -68,20 +88,38 @@ pub async fn entrypoint(
         program_test.run();
     }
 
+    if !differential_tests.is_empty() {
+        let differential_test = BoomerangDifferentialTest::new(programs, differential_tests, tests);
+        differential_test.run();
+    }
+
     if !integration_tests.is_empty() {
         let programs = select_test_programs(programs, integration_tests);
-        let integration_test = BoomerangIntegrationTest::new(&programs, tests);
+        let integration_test = BoomerangIntegrationTest::new(&programs, tests, validator_pool_size);
         integration_test.run();
     }
 
+    if !replay_tests.is_empty() {
+        let programs = select_test_programs(programs, replay_tests);
+        let replay_test = BoomerangReplayTest::new(&programs, tests, validator_pool_size);
+        replay_test.run();
+    }
+
     if !migration_tests.is_empty() {
         let migration_test_programs = migration_tests.iter().map(|(p, _)| *p).collect::<Vec<_>>();
         let programs = select_test_programs(programs, &migration_test_programs);
         let migration_tests = migration_tests
             .iter()
-            .map(|(p, t)| (*p, *t, programs.iter().find(|(p, _)| p == p).unwrap().1))
+            .map(|(program_file, target_program)| {
+                let (_, program_id) = programs
+                    .iter()
+                    .find(|(name, _)| name == program_file)
+                    .unwrap();
+                (*program_file, *program_id, *target_program)
+            })
             .collect::<Vec<_>>();
-        let migration_test = BoomerangMigrationTest::new(&migration_tests, tests).await;
+        let migration_test =
+            BoomerangMigrationTest::new(&migration_tests, tests, migration_test_mode).await;
         migration_test.run().await;
     }
 }
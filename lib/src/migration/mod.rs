@@ -1,43 +1,77 @@
 use std::path::Path;
 
 mod setup;
+pub(crate) mod snapshot;
 
 use {
     crate::{
-        dirs, output, program::BoomerangProgramTestIteration,
-        validator_options::IntoTestValidatorStartOptions, BoomerangTests,
+        dirs, output,
+        pool::ValidatorPoolSlot,
+        program::{BoomerangProgramTestChunk, BoomerangProgramTestIteration, TrialOutcome},
+        validator_options::IntoTestValidatorStartOptions,
+        BoomerangTests, NativeProgram,
     },
-    solana_boomerang_client::{BoomerangClient, BoomerangTestClient},
+    snapshot::AccountSnapshot,
+    solana_boomerang_client::{BoomerangClient, BoomerangTestClient, BoomerangTestClientConfig},
     solana_boomerang_test_validator::{commands::run_command, BoomerangTestValidator},
-    solana_sdk::{pubkey::Pubkey, signer::Signer},
-    std::str::FromStr,
+    solana_sdk::{feature, pubkey::Pubkey, signature::Keypair, signer::Signer},
+    std::{ffi::OsStr, str::FromStr},
 };
 
 const SLOTS_PER_EPOCH: u64 = 120;
 
+/// How a migration test exercises the target program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MigrationTestMode {
+    /// Activate the migration feature, then run each chunk against the
+    /// migrated program.
+    /// Only the accounts a chunk loads at genesis exist when the migration
+    /// activates, so only those are checked to be preserved by it.
+    #[default]
+    AfterActivation,
+    /// Run each chunk against the native program, activate the migration
+    /// feature, then rerun the same trials against the migrated program and
+    /// compare the two sets of results
+    BeforeAndAfter,
+}
+
 pub struct BoomerangMigrationTest {
     migrations: Vec<(
         BoomerangProgramTestIteration,
-        String, // Target program
+        Pubkey,        // Source program ID
+        NativeProgram, // Target program
     )>,
+    mode: MigrationTestMode,
     solana_cli_alias: String,
     solana_test_validator_alias: String,
 }
 
 impl BoomerangMigrationTest {
-    pub async fn new(migrations: &[(&str, &str, &str)], tests: BoomerangTests<'_>) -> Self {
+    pub async fn new(
+        migrations: &[(&str, &str, NativeProgram)],
+        tests: BoomerangTests<'_>,
+        mode: MigrationTestMode,
+    ) -> Self {
         Self {
             migrations: migrations
                 .iter()
                 .map(|(program_file, program_id, target_program)| {
+                    // A program declared at its target's address is deployed
+                    // at a stand-in address, since the native program is
+                    // there until the migration activates
+                    let mut source_program_id = Pubkey::from_str(program_id).unwrap();
+                    if source_program_id == target_program.program_id() {
+                        source_program_id = Keypair::new().pubkey();
+                    }
                     let iteration = BoomerangProgramTestIteration::new(
-                        &(program_file, program_id),
+                        &(program_file, &source_program_id.to_string()),
                         tests,
                         /* use_banks */ false,
                     );
-                    (iteration, target_program.to_string())
+                    (iteration, source_program_id, *target_program)
                 })
                 .collect(),
+            mode,
             solana_cli_alias: dirs::solana_cli_path_string(),
             solana_test_validator_alias: dirs::solana_test_validator_path_string(),
         }
@@ -45,52 +79,159 @@ impl BoomerangMigrationTest {
 
     async fn activate_feature_and_poll_for_activation(
         solana_cli_alias: &str,
-        client: &BoomerangClient,
+        client: &mut BoomerangClient,
+        feature_id: &Pubkey,
         feature_keypair_path: &Path,
     ) {
-        run_command(&format!(
-            "{} feature activate {} development",
+        run_command(
             solana_cli_alias,
-            feature_keypair_path.to_str().unwrap(),
-        ));
+            [
+                OsStr::new("feature"),
+                OsStr::new("activate"),
+                feature_keypair_path.as_os_str(),
+                OsStr::new("development"),
+            ],
+        )
+        .unwrap_or_else(|err| panic!("Failed to activate migration feature: {}", err));
         client.poll_for_next_epoch().await.unwrap();
         client.poll_slots(5).await.unwrap();
+
+        let activated_at = client
+            .get_account(feature_id)
+            .await
+            .unwrap()
+            .and_then(|account| feature::from_account(&account))
+            .and_then(|feature| feature.activated_at);
+        assert!(
+            activated_at.is_some(),
+            "Migration feature {} was not activated at the epoch boundary",
+            feature_id
+        );
+    }
+
+    /// Activate the migration, snapshotting every account owned by the
+    /// target program immediately before and after.
+    /// Returns whether the migration changed any of those accounts in an
+    /// unexpected way.
+    /// On a fresh validator the native program owns only the accounts loaded
+    /// at genesis, so the comparison is only meaningful with staged or fixture
+    /// accounts, or after trials have run against the native program.
+    async fn migrate_and_diff_accounts(
+        &self,
+        config: &BoomerangTestClientConfig,
+        feature_id: &Pubkey,
+        feature_keypair_path: &Path,
+    ) -> bool {
+        let mut client = BoomerangClient::new(config, /* use_banks */ false).await;
+
+        let before = AccountSnapshot::capture(&mut client, &config.program_id).await;
+
+        Self::activate_feature_and_poll_for_activation(
+            &self.solana_cli_alias,
+            &mut client,
+            feature_id,
+            feature_keypair_path,
+        )
+        .await;
+
+        let after = AccountSnapshot::capture(&mut client, &config.program_id).await;
+
+        let diffs = before.diff(&after);
+        output::account_snapshot_diffs(&config.program_id, before.len(), &diffs);
+
+        diffs.iter().any(|diff| !diff.is_expected())
+    }
+
+    /// Run the chunk's trials against the native program, activate the
+    /// migration, then rerun them against the migrated program.
+    /// Returns whether the migration changed the program's accounts
+    /// unexpectedly, or any trial failed after the migration or had a
+    /// different outcome than before it.
+    async fn run_before_and_after(
+        &self,
+        chunk: &BoomerangProgramTestChunk,
+        feature_id: &Pubkey,
+        feature_keypair_path: &Path,
+    ) -> bool {
+        let before = chunk.run_each();
+
+        let accounts_diverged = self
+            .migrate_and_diff_accounts(chunk.config(), feature_id, feature_keypair_path)
+            .await;
+
+        let after = chunk.run_each();
+
+        let results = before
+            .into_iter()
+            .zip(after)
+            .map(|((name, before), (_, after))| (name, before, after))
+            .collect::<Vec<_>>();
+        output::migration_results(&results);
+
+        accounts_diverged
+            || results
+                .iter()
+                .any(|(_, before, after)| before != after || *after == TrialOutcome::Failed)
     }
 
-    pub async fn run(self) {
-        for migration in self.migrations {
-            let (iteration, target_program) = migration;
-            let (feature_keypair, feature_keypair_path) = setup::setup(&target_program);
-            let feature_id = feature_keypair.pubkey().to_string();
+    pub async fn run(mut self) {
+        let migrations = std::mem::take(&mut self.migrations);
+        for (iteration, source_program_id, target_program) in migrations {
+            let (feature_keypair, feature_keypair_path) =
+                setup::setup(&target_program, &source_program_id);
+            let feature_id = feature_keypair.pubkey();
 
             output::starting_migration_tests(iteration.program_file(), &target_program);
 
-            for chunk in iteration.chunks() {
-                let mut config = chunk.config().clone();
-                config
-                    .features_disabled
-                    .push(Pubkey::from_str(&feature_id).unwrap());
-                config.slots_per_epoch = SLOTS_PER_EPOCH;
+            let slot = ValidatorPoolSlot::serial();
+
+            for mut chunk in iteration.chunks() {
+                let config = chunk.config_mut();
+                config.features_disabled.push(feature_id);
+                config.slots_per_epoch = Some(SLOTS_PER_EPOCH);
 
-                let test_validator = BoomerangTestValidator::new(
-                    dirs::test_ledger_path(),
+                // The source program is deployed at its own address, but once
+                // migrated it lives at the target's address, so that's where
+                // the trials must send their instructions
+                let mut test_validator = BoomerangTestValidator::new(
+                    slot.ledger_path(),
                     &self.solana_cli_alias,
                     &self.solana_test_validator_alias,
-                    &[&config.to_test_validator_start_options()],
+                    &[
+                        &config.to_test_validator_start_options(),
+                        &slot.stage_accounts(config),
+                        &config.validator_start_options,
+                    ],
                 );
+                config.program_id = target_program.program_id();
+                config.rpc_endpoint = test_validator.rpc_url().to_string();
+
                 test_validator.solana_test_validator_teardown();
                 test_validator.solana_test_validator_start();
 
-                Self::activate_feature_and_poll_for_activation(
-                    &self.solana_cli_alias,
-                    &BoomerangClient::new(&config, /* use_banks */ false).await,
-                    &feature_keypair_path,
-                )
-                .await;
-
-                chunk.run();
+                let failed = match self.mode {
+                    MigrationTestMode::AfterActivation => {
+                        let accounts_diverged = self
+                            .migrate_and_diff_accounts(
+                                chunk.config(),
+                                &feature_id,
+                                &feature_keypair_path,
+                            )
+                            .await;
+                        chunk.run_without_exit().has_failed() || accounts_diverged
+                    }
+                    MigrationTestMode::BeforeAndAfter => {
+                        self.run_before_and_after(&chunk, &feature_id, &feature_keypair_path)
+                            .await
+                    }
+                };
 
                 test_validator.solana_test_validator_teardown();
+                slot.remove_staged_accounts();
+
+                if failed {
+                    std::process::exit(101);
+                }
             }
         }
     }
@@ -1,31 +1,246 @@
 use {
-    crate::dirs,
+    crate::{dirs, output, NativeProgram},
     solana_boomerang_test_validator::commands::{run_command, run_command_with_dir},
-    solana_sdk::signature::Keypair,
-    std::path::PathBuf,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer},
+    std::{
+        ffi::OsStr,
+        path::{Path, PathBuf},
+    },
 };
 
 const SOLANA_REPOSITORY: &str = "https://github.com/buffalojoe/solana.git";
 const SOLANA_BRANCH: &str = "boomerang";
 
-pub fn setup(_target_program: &str) -> (Keypair, PathBuf) {
-    let solana_install_path = dirs::solana_install_path();
+/// The module in the Solana fork that declares the Boomerang migration.
+///
+/// The `boomerang` branch ships a placeholder at this path and registers the
+/// migration it declares alongside the runtime's builtin migrations, so
+/// overwriting this file is all that's needed to key a native-to-BPF program
+/// migration to a new feature ID.
+const MIGRATION_CONFIG_PATH: &str = "runtime/src/bank/migrate_native_program/boomerang.rs";
+/// The module in the Solana fork that registers the Boomerang migration
+const MIGRATION_REGISTRATION_PATH: &str = "runtime/src/bank/migrate_native_program/mod.rs";
+/// The items the generated migration config declares, which the fork's
+/// placeholder must also declare for its registration code to compile
+/// against either
+const MIGRATION_CONFIG_ITEMS: &[&str] = &[
+    "BOOMERANG_MIGRATION_FEATURE_ID",
+    "BOOMERANG_MIGRATION_TARGET",
+    "BOOMERANG_MIGRATION_SOURCE_PROGRAM_ID",
+];
 
-    // Clear any local changes
-    run_command_with_dir("git reset --hard", &solana_install_path);
+/// The variants of the fork's `NativeProgram` enum, declared in one of the
+/// migration module's files, or `None` if no file declares it.
+/// Panics if one of the files can't be parsed, rather than risk missing the
+/// enum in it.
+fn fork_native_programs(solana_install_path: &Path) -> Option<Vec<String>> {
+    let module_path = solana_install_path
+        .join(MIGRATION_REGISTRATION_PATH)
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    std::fs::read_dir(&module_path)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension() == Some(OsStr::new("rs")))
+        .find_map(|path| {
+            let source = std::fs::read_to_string(&path)
+                .unwrap_or_else(|err| panic!("Failed to read {:?}: {}", path, err));
+            let file = syn::parse_file(&source)
+                .unwrap_or_else(|err| panic!("Failed to parse {:?}: {}", path, err));
+            native_program_variants(&file.items)
+        })
+}
 
-    // Fetch the latest changes
-    if solana_install_path.exists() {
-        run_command_with_dir(
-            &format!("git checkout {}", SOLANA_BRANCH),
-            &solana_install_path,
+/// The variants of the `NativeProgram` enum declared among `items`, or in
+/// any module declared inline among them
+fn native_program_variants(items: &[syn::Item]) -> Option<Vec<String>> {
+    items.iter().find_map(|item| match item {
+        syn::Item::Enum(item) if item.ident == "NativeProgram" => Some(
+            item.variants
+                .iter()
+                .map(|variant| variant.ident.to_string())
+                .collect(),
+        ),
+        syn::Item::Mod(syn::ItemMod {
+            content: Some((_, items)),
+            ..
+        }) => native_program_variants(items),
+        _ => None,
+    })
+}
+
+/// The revision of the Solana fork currently checked out
+fn checked_out_revision(solana_install_path: &Path) -> String {
+    // The fork is always checked out detached, so `HEAD` holds the revision
+    // itself rather than a branch reference
+    let head_path = solana_install_path.join(".git").join("HEAD");
+    let head = std::fs::read_to_string(&head_path)
+        .unwrap_or_else(|err| panic!("Failed to read {:?}: {}", head_path, err));
+    head.trim().to_string()
+}
+
+/// Check that the checked-out fork has the migration hook Boomerang writes
+/// its config into, before overwriting anything.
+/// Panics with the missing piece if not, rather than leaving it to surface as
+/// a compile error deep in the fork's build.
+fn check_migration_hook(
+    solana_install_path: &Path,
+    revision: &str,
+    target_program: &NativeProgram,
+) {
+    let read = |path: &str| {
+        std::fs::read_to_string(solana_install_path.join(path)).unwrap_or_else(|_| {
+            panic!(
+                "Solana fork revision {} has no `{}`, so it doesn't have Boomerang's migration \
+                 hook. Pin a revision of the `{}` branch that does in {:?}.",
+                revision,
+                path,
+                SOLANA_BRANCH,
+                dirs::solana_revision_lock_path(),
+            )
+        })
+    };
+    let placeholder = read(MIGRATION_CONFIG_PATH);
+    let registration = read(MIGRATION_REGISTRATION_PATH);
+    if !registration.contains("mod boomerang;") {
+        panic!(
+            "Solana fork revision {} doesn't declare `mod boomerang;` in `{}`, so the migration \
+             config would never be compiled",
+            revision, MIGRATION_REGISTRATION_PATH,
+        );
+    }
+    for item in MIGRATION_CONFIG_ITEMS {
+        if !placeholder.contains(item) {
+            panic!(
+                "Solana fork revision {}'s placeholder `{}` doesn't declare `{}`",
+                revision, MIGRATION_CONFIG_PATH, item,
+            );
+        }
+    }
+
+    // The generated config names the target by its `NativeProgram` variant,
+    // which must exist in the fork's enum
+    let native_programs = fork_native_programs(solana_install_path).unwrap_or_else(|| {
+        panic!(
+            "Solana fork revision {} doesn't declare `enum NativeProgram` alongside `{}`",
+            revision, MIGRATION_REGISTRATION_PATH,
+        )
+    });
+    if native_programs.is_empty() {
+        panic!(
+            "Solana fork revision {}'s `enum NativeProgram` has no variants",
+            revision,
         );
-        run_command_with_dir("git pull", &solana_install_path);
+    }
+    if !native_programs.contains(&target_program.variant_name()) {
+        panic!(
+            "Solana fork revision {} has no `{}` to migrate. Its native programs are: {}",
+            revision,
+            target_program,
+            native_programs.join(", "),
+        );
+    }
+}
+
+/// Render the fork's migration config module, declaring that activating
+/// `feature_id` migrates `target_program` to the BPF program deployed at
+/// `source_program_id`
+fn migration_config_source(
+    feature_id: &Pubkey,
+    target_program: &NativeProgram,
+    source_program_id: &Pubkey,
+) -> String {
+    format!(
+        r#"//! Generated by Boomerang. Do not edit.
+
+use {{
+    super::NativeProgram,
+    solana_sdk::{{pubkey, pubkey::Pubkey}},
+}};
+
+/// Activating this feature replaces the target native program with the BPF
+/// program deployed at the source address
+pub(crate) const BOOMERANG_MIGRATION_FEATURE_ID: Pubkey = pubkey!("{}");
+
+/// The native program to migrate
+pub(crate) const BOOMERANG_MIGRATION_TARGET: NativeProgram = {};
+
+/// The address of the BPF program to migrate the native program to
+pub(crate) const BOOMERANG_MIGRATION_SOURCE_PROGRAM_ID: Pubkey = pubkey!("{}");
+"#,
+        feature_id, target_program, source_program_id,
+    )
+}
+
+/// Add the feature ID to the bank as a native program migration
+fn write_migration_config(
+    solana_install_path: &Path,
+    feature_id: &Pubkey,
+    target_program: &NativeProgram,
+    source_program_id: &Pubkey,
+) {
+    let config_path = solana_install_path.join(MIGRATION_CONFIG_PATH);
+    std::fs::write(
+        &config_path,
+        migration_config_source(feature_id, target_program, source_program_id),
+    )
+    .expect("Failed to write migration config");
+}
+
+/// Prepare the Solana fork used for migration tests.
+///
+/// Checks out the revision pinned in the workspace's Solana revision lock,
+/// or the latest `boomerang` branch, pinning it, if there is no lock yet.
+/// Then checks that revision has the migration hook, generates a fresh feature
+/// keypair, registers it as the feature gating the migration of
+/// `target_program` to the BPF program at `source_program_id`, and builds
+/// the fork's CLI and test validator.
+pub fn setup(target_program: &NativeProgram, source_program_id: &Pubkey) -> (Keypair, PathBuf) {
+    let solana_install_path = dirs::solana_install_path();
+
+    // Fetch the latest changes, clearing any local changes (including a
+    // previously generated migration config)
+    if solana_install_path.exists() {
+        run_command_with_dir("git", ["reset", "--hard"], &solana_install_path)
+            .unwrap_or_else(|err| panic!("Failed to reset Solana fork: {}", err));
+        run_command_with_dir("git", ["fetch", "origin"], &solana_install_path)
+            .unwrap_or_else(|err| panic!("Failed to fetch Solana fork: {}", err));
     } else {
-        run_command(&format!(
-            "git clone {} --branch {}",
-            SOLANA_REPOSITORY, SOLANA_BRANCH,
-        ));
+        run_command(
+            "git",
+            [
+                OsStr::new("clone"),
+                OsStr::new(SOLANA_REPOSITORY),
+                OsStr::new("--branch"),
+                OsStr::new(SOLANA_BRANCH),
+                solana_install_path.as_os_str(),
+            ],
+        )
+        .unwrap_or_else(|err| panic!("Failed to clone Solana fork: {}", err));
+    }
+
+    let revision_lock_path = dirs::solana_revision_lock_path();
+    let pinned_revision = std::fs::read_to_string(&revision_lock_path)
+        .ok()
+        .map(|revision| revision.trim().to_string());
+    let revision = pinned_revision
+        .clone()
+        .unwrap_or_else(|| format!("origin/{}", SOLANA_BRANCH));
+    run_command_with_dir(
+        "git",
+        ["checkout", "--detach", &revision],
+        &solana_install_path,
+    )
+    .unwrap_or_else(|err| panic!("Failed to check out Solana fork at {}: {}", revision, err));
+    let revision = checked_out_revision(&solana_install_path);
+
+    check_migration_hook(&solana_install_path, &revision, target_program);
+
+    if pinned_revision.is_none() {
+        std::fs::write(&revision_lock_path, format!("{}\n", revision))
+            .unwrap_or_else(|err| panic!("Failed to write {:?}: {}", revision_lock_path, err));
+        output::pinned_solana_revision(&revision, &revision_lock_path);
     }
 
     // Generate a keypair for the feature ID
@@ -38,10 +253,63 @@ pub fn setup(_target_program: &str) -> (Keypair, PathBuf) {
     dirs::write_keypair_to_path(&feature_keypair, &feature_keypair_path);
 
     // Add the feature ID to the bank as a native program migration
-    // unimplemented!()
+    write_migration_config(
+        &solana_install_path,
+        &feature_keypair.pubkey(),
+        target_program,
+        source_program_id,
+    );
 
     // Build Solana
-    run_command_with_dir("./cargo build", &solana_install_path);
+    run_command_with_dir("./cargo", ["build"], &solana_install_path)
+        .unwrap_or_else(|err| panic!("Failed to build Solana fork: {}", err));
 
     (feature_keypair, feature_keypair_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants(source: &str) -> Option<Vec<String>> {
+        native_program_variants(&syn::parse_file(source).unwrap().items)
+    }
+
+    #[test]
+    fn test_native_program_variants_skip_attributes_and_comments() {
+        let source = r#"
+            /// Programs that can be migrated, e.g. `{ AddressLookupTable }`
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub enum NativeProgram {
+                /// The address lookup table program { lookup tables }
+                AddressLookupTable,
+                #[allow(dead_code)]
+                Config, // { config }
+                FeatureGate
+            }
+        "#;
+        assert_eq!(
+            variants(source).unwrap(),
+            vec!["AddressLookupTable", "Config", "FeatureGate"],
+        );
+    }
+
+    #[test]
+    fn test_native_program_variants_found_in_inline_modules() {
+        let source = r#"
+            pub(crate) mod native {
+                pub enum NativeProgram { Stake }
+            }
+        "#;
+        assert_eq!(variants(source).unwrap(), vec!["Stake"]);
+    }
+
+    #[test]
+    fn test_native_program_variants_ignore_other_enums() {
+        let source = r#"
+            pub enum CoreBpfMigrationTargetType { Builtin, Stateless }
+            struct NativeProgram;
+        "#;
+        assert_eq!(variants(source), None);
+    }
+}
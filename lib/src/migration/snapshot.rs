@@ -0,0 +1,313 @@
+use {
+    solana_boomerang_client::BoomerangTestClient,
+    solana_sdk::{account::Account, clock::Epoch, pubkey::Pubkey},
+    std::{collections::BTreeMap, fmt},
+};
+
+/// A difference in a single field of an account across a migration
+#[derive(Debug, PartialEq, Eq)]
+pub enum AccountFieldDiff {
+    Lamports { before: u64, after: u64 },
+    Owner { before: Pubkey, after: Pubkey },
+    Data { before: Vec<u8>, after: Vec<u8> },
+    Executable { before: bool, after: bool },
+    RentEpoch { before: Epoch, after: Epoch },
+}
+impl AccountFieldDiff {
+    /// Whether the difference can legitimately occur across a migration.
+    /// Rent collection may bump an account's rent epoch at the epoch
+    /// boundary, but every other field must be preserved.
+    pub fn is_expected(&self) -> bool {
+        matches!(self, Self::RentEpoch { .. })
+    }
+}
+impl fmt::Display for AccountFieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lamports { before, after } => write!(f, "lamports: {} -> {}", before, after),
+            Self::Owner { before, after } => write!(f, "owner: {} -> {}", before, after),
+            Self::Data { before, after } => {
+                let first_difference = before
+                    .iter()
+                    .zip(after.iter())
+                    .position(|(b, a)| b != a)
+                    .unwrap_or(before.len().min(after.len()));
+                write!(
+                    f,
+                    "data: {} bytes -> {} bytes, first difference at byte {}",
+                    before.len(),
+                    after.len(),
+                    first_difference,
+                )
+            }
+            Self::Executable { before, after } => {
+                write!(f, "executable: {} -> {}", before, after)
+            }
+            Self::RentEpoch { before, after } => {
+                write!(f, "rent epoch: {} -> {}", before, after)
+            }
+        }
+    }
+}
+
+/// A difference in a single account across a migration
+#[derive(Debug, PartialEq, Eq)]
+pub enum AccountDiff {
+    /// The account existed before the migration but not after
+    Removed { pubkey: Pubkey },
+    /// The account did not exist before the migration but did after
+    Added { pubkey: Pubkey },
+    /// The account existed on both sides of the migration with different
+    /// state
+    Changed {
+        pubkey: Pubkey,
+        fields: Vec<AccountFieldDiff>,
+    },
+}
+impl AccountDiff {
+    pub fn is_expected(&self) -> bool {
+        match self {
+            Self::Removed { .. } | Self::Added { .. } => false,
+            Self::Changed { fields, .. } => fields.iter().all(AccountFieldDiff::is_expected),
+        }
+    }
+}
+impl fmt::Display for AccountDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Removed { pubkey } => write!(f, "{}: removed", pubkey),
+            Self::Added { pubkey } => write!(f, "{}: added", pubkey),
+            Self::Changed { pubkey, fields } => {
+                write!(f, "{}:", pubkey)?;
+                for field in fields {
+                    write!(f, "\n        {}", field)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn diff_account(before: &Account, after: &Account) -> Vec<AccountFieldDiff> {
+    let mut fields = vec![];
+    if before.lamports != after.lamports {
+        fields.push(AccountFieldDiff::Lamports {
+            before: before.lamports,
+            after: after.lamports,
+        });
+    }
+    if before.owner != after.owner {
+        fields.push(AccountFieldDiff::Owner {
+            before: before.owner,
+            after: after.owner,
+        });
+    }
+    if before.data != after.data {
+        fields.push(AccountFieldDiff::Data {
+            before: before.data.clone(),
+            after: after.data.clone(),
+        });
+    }
+    if before.executable != after.executable {
+        fields.push(AccountFieldDiff::Executable {
+            before: before.executable,
+            after: after.executable,
+        });
+    }
+    if before.rent_epoch != after.rent_epoch {
+        fields.push(AccountFieldDiff::RentEpoch {
+            before: before.rent_epoch,
+            after: after.rent_epoch,
+        });
+    }
+    fields
+}
+
+/// The state of every account owned by a program at a point in time
+pub struct AccountSnapshot {
+    accounts: BTreeMap<Pubkey, Account>,
+}
+impl AccountSnapshot {
+    /// Capture all accounts owned by `program_id` via `getProgramAccounts`
+    pub async fn capture<C: BoomerangTestClient + Send>(
+        client: &mut C,
+        program_id: &Pubkey,
+    ) -> Self {
+        let accounts = client
+            .get_program_accounts(program_id)
+            .await
+            .expect("Failed to snapshot program accounts")
+            .into_iter()
+            .collect();
+        Self { accounts }
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Compare this snapshot to one taken later, returning every account
+    /// that was added, removed or changed, ordered by address
+    pub fn diff(&self, after: &Self) -> Vec<AccountDiff> {
+        let mut diffs = vec![];
+        for (pubkey, before_account) in &self.accounts {
+            match after.accounts.get(pubkey) {
+                Some(after_account) => {
+                    let fields = diff_account(before_account, after_account);
+                    if !fields.is_empty() {
+                        diffs.push(AccountDiff::Changed {
+                            pubkey: *pubkey,
+                            fields,
+                        });
+                    }
+                }
+                None => diffs.push(AccountDiff::Removed { pubkey: *pubkey }),
+            }
+        }
+        for pubkey in after.accounts.keys() {
+            if !self.accounts.contains_key(pubkey) {
+                diffs.push(AccountDiff::Added { pubkey: *pubkey });
+            }
+        }
+        diffs.sort_by_key(|diff| match diff {
+            AccountDiff::Removed { pubkey }
+            | AccountDiff::Added { pubkey }
+            | AccountDiff::Changed { pubkey, .. } => *pubkey,
+        });
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_boomerang_client::fixtures::account};
+
+    fn snapshot(accounts: &[(Pubkey, Account)]) -> AccountSnapshot {
+        AccountSnapshot {
+            accounts: accounts.iter().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_identical_snapshots() {
+        let owner = Pubkey::new_unique();
+        let before = snapshot(&[(Pubkey::new_unique(), account(1, owner, &[1, 2], 0))]);
+        let after = snapshot(&before.accounts.clone().into_iter().collect::<Vec<_>>());
+        assert_eq!(before.diff(&after), vec![]);
+    }
+
+    #[test]
+    fn test_diff_added_and_removed() {
+        let owner = Pubkey::new_unique();
+        let removed = Pubkey::new_unique();
+        let added = Pubkey::new_unique();
+        let before = snapshot(&[(removed, account(1, owner, &[], 0))]);
+        let after = snapshot(&[(added, account(1, owner, &[], 0))]);
+
+        let diffs = before.diff(&after);
+        assert_eq!(diffs.len(), 2);
+        assert!(diffs.contains(&AccountDiff::Removed { pubkey: removed }));
+        assert!(diffs.contains(&AccountDiff::Added { pubkey: added }));
+        assert!(diffs.iter().all(|diff| !diff.is_expected()));
+    }
+
+    #[test]
+    fn test_diff_changed_fields() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let before = snapshot(&[(pubkey, account(1, owner, &[1, 2], 0))]);
+        let after = snapshot(&[(pubkey, account(2, new_owner, &[1, 3], 0))]);
+
+        let diffs = before.diff(&after);
+        assert_eq!(
+            diffs,
+            vec![AccountDiff::Changed {
+                pubkey,
+                fields: vec![
+                    AccountFieldDiff::Lamports {
+                        before: 1,
+                        after: 2
+                    },
+                    AccountFieldDiff::Owner {
+                        before: owner,
+                        after: new_owner
+                    },
+                    AccountFieldDiff::Data {
+                        before: vec![1, 2],
+                        after: vec![1, 3]
+                    },
+                ],
+            }]
+        );
+        assert!(!diffs[0].is_expected());
+    }
+
+    #[test]
+    fn test_diff_single_changed_field_is_unexpected() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let before = account(1, owner, &[1], 0);
+        for after in [
+            account(2, owner, &[1], 0),
+            account(1, Pubkey::new_unique(), &[1], 0),
+            account(1, owner, &[2], 0),
+            Account {
+                executable: true,
+                ..before.clone()
+            },
+        ] {
+            let diffs = snapshot(&[(pubkey, before.clone())]).diff(&snapshot(&[(pubkey, after)]));
+            assert_eq!(diffs.len(), 1);
+            assert!(!diffs[0].is_expected());
+        }
+    }
+
+    #[test]
+    fn test_diff_rent_epoch_only_is_expected() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let before = snapshot(&[(pubkey, account(1, owner, &[1], 0))]);
+        let after = snapshot(&[(pubkey, account(1, owner, &[1], 1))]);
+
+        let diffs = before.diff(&after);
+        assert_eq!(
+            diffs,
+            vec![AccountDiff::Changed {
+                pubkey,
+                fields: vec![AccountFieldDiff::RentEpoch {
+                    before: 0,
+                    after: 1
+                }],
+            }]
+        );
+        assert!(diffs[0].is_expected());
+    }
+
+    #[test]
+    fn test_diff_ordered_by_address() {
+        let owner = Pubkey::new_unique();
+        let mut pubkeys = (0..4).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let before = snapshot(&[
+            (pubkeys[0], account(1, owner, &[], 0)),
+            (pubkeys[1], account(1, owner, &[], 0)),
+        ]);
+        let after = snapshot(&[
+            (pubkeys[1], account(2, owner, &[], 0)),
+            (pubkeys[2], account(1, owner, &[], 0)),
+            (pubkeys[3], account(1, owner, &[], 0)),
+        ]);
+
+        let diffs = before.diff(&after);
+        let diff_pubkeys = diffs
+            .iter()
+            .map(|diff| match diff {
+                AccountDiff::Removed { pubkey }
+                | AccountDiff::Added { pubkey }
+                | AccountDiff::Changed { pubkey, .. } => *pubkey,
+            })
+            .collect::<Vec<_>>();
+        pubkeys.sort();
+        assert_eq!(diff_pubkeys, pubkeys);
+    }
+}
@@ -0,0 +1,72 @@
+use {
+    solana_sdk::{
+        address_lookup_table, bpf_loader, bpf_loader_upgradeable, compute_budget, config,
+        ed25519_program, feature, loader_v4, native_loader, pubkey::Pubkey, secp256k1_program,
+        stake, system_program, vote,
+    },
+    std::fmt,
+};
+
+/// Declares `NativeProgram` from a single list of variants and their
+/// addresses, so the enum, `ALL` and `program_id` can't drift apart
+macro_rules! native_programs {
+    ($($variant:ident => $program_id:expr,)*) => {
+        /// A native program that can be the target of a migration test.
+        ///
+        /// Variants mirror the runtime's `NativeProgram` enum, so a target's
+        /// `Display` form (`NativeProgram::AddressLookupTable`) is also how the
+        /// runtime refers to it. Migration setup checks the target against the
+        /// Solana fork's enum before building it.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum NativeProgram {
+            $($variant,)*
+        }
+        impl NativeProgram {
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// The canonical address of the native program
+            pub fn program_id(&self) -> Pubkey {
+                match self {
+                    $(Self::$variant => $program_id,)*
+                }
+            }
+        }
+    };
+}
+
+native_programs! {
+    AddressLookupTable => address_lookup_table::program::id(),
+    BpfLoader => bpf_loader::id(),
+    BpfLoaderUpgradeable => bpf_loader_upgradeable::id(),
+    ComputeBudget => compute_budget::id(),
+    Config => config::program::id(),
+    Ed25519 => ed25519_program::id(),
+    FeatureGate => feature::id(),
+    LoaderV4 => loader_v4::id(),
+    NativeLoader => native_loader::id(),
+    Secp256k1 => secp256k1_program::id(),
+    Stake => stake::program::id(),
+    System => system_program::id(),
+    Vote => vote::program::id(),
+    ZkTokenProof => solana_sdk::pubkey!("ZkTokenProof1111111111111111111111111111111"),
+}
+
+impl NativeProgram {
+    /// The native program deployed at `program_id`, if any
+    pub fn from_program_id(program_id: &Pubkey) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|native_program| native_program.program_id() == *program_id)
+    }
+
+    /// The variant's name, as the runtime's `NativeProgram` enum names it
+    pub fn variant_name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+impl fmt::Display for NativeProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeProgram::{:?}", self)
+    }
+}
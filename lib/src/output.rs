@@ -1,5 +1,10 @@
 use {
-    std::io::Write,
+    crate::{migration::snapshot::AccountDiff, program::TrialOutcome, NativeProgram},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
     termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor},
 };
 
@@ -38,16 +43,165 @@ pub fn starting_integration_tests(program: &str) {
     );
 }
 
-pub fn starting_migration_tests(source_program: &str, target: &str) {
+pub fn starting_migration_tests(source_program: &str, target: &NativeProgram) {
     boomerang(
         &format!(
-            "Starting migration tests for {} against {}",
-            source_program, target
+            "Starting migration tests for {} against {} ({})",
+            source_program,
+            target,
+            target.program_id(),
         ),
         Color::Cyan,
     );
 }
 
+pub fn pinned_solana_revision(revision: &str, lock_path: &Path) {
+    boomerang(
+        &format!(
+            "Pinned the Solana fork to {} in {:?}. Delete it to update the fork.",
+            revision, lock_path
+        ),
+        Color::Yellow,
+    );
+}
+
+pub fn starting_differential_tests(program: &str, differential_program: &str) {
+    boomerang(
+        &format!(
+            "Starting differential tests for {} against {}",
+            program, differential_program
+        ),
+        Color::Cyan,
+    );
+}
+
+pub fn recording_program_tests(program: &str) {
+    boomerang(
+        &format!("Recording program tests for {}", program),
+        Color::Cyan,
+    );
+}
+
+pub fn replaying_recordings() {
+    boomerang(
+        "Replaying the recorded program tests against a test validator",
+        Color::Cyan,
+    );
+}
+
+pub fn cloned_account_fixtures(url: &str, directory: &Path, paths: &[PathBuf]) {
+    boomerang(
+        &format!(
+            "Cloned {} accounts from {} to {:?}",
+            paths.len(),
+            url,
+            directory
+        ),
+        Color::Green,
+    );
+}
+
 pub fn chunk(i: usize, total: usize) {
     boomerang(&format!("Round {} of {}", i, total), Color::Cyan);
 }
+
+fn trial_outcome_label(outcome: TrialOutcome) -> (&'static str, Color) {
+    match outcome {
+        TrialOutcome::Passed => ("ok", Color::Green),
+        TrialOutcome::Failed => ("FAILED", Color::Red),
+        TrialOutcome::Ignored => ("ignored", Color::Yellow),
+    }
+}
+
+fn migration_results_output(
+    results: &[(String, TrialOutcome, TrialOutcome)],
+) -> Result<(), std::io::Error> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    let name_width = results
+        .iter()
+        .map(|(name, ..)| name.len())
+        .max()
+        .unwrap_or_default();
+    writeln!(
+        &mut stdout,
+        "    {:<name_width$}  {:<8}  {:<8}",
+        "trial", "native", "migrated",
+    )?;
+    for (name, before, after) in results {
+        write!(&mut stdout, "    {:<name_width$}  ", name)?;
+        for outcome in [before, after] {
+            let (label, color) = trial_outcome_label(*outcome);
+            stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
+            write!(&mut stdout, "{:<8}  ", label)?;
+            stdout.reset()?;
+        }
+        if before != after {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+            write!(&mut stdout, "diverged")?;
+            stdout.reset()?;
+        }
+        writeln!(&mut stdout)?;
+    }
+    println!();
+    Ok(())
+}
+
+pub fn migration_results(results: &[(String, TrialOutcome, TrialOutcome)]) {
+    boomerang("Trial results before and after the migration", Color::Cyan);
+    migration_results_output(results).unwrap();
+}
+
+fn account_snapshot_diffs_output(diffs: &[AccountDiff]) -> Result<(), std::io::Error> {
+    let mut stdout = StandardStream::stdout(ColorChoice::Always);
+    for diff in diffs {
+        let color = if diff.is_expected() {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+        stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
+        writeln!(&mut stdout, "    {}", diff)?;
+        stdout.reset()?;
+    }
+    println!();
+    Ok(())
+}
+
+pub fn account_snapshot_diffs(program_id: &Pubkey, num_accounts: usize, diffs: &[AccountDiff]) {
+    if num_accounts == 0 && diffs.is_empty() {
+        boomerang(
+            &format!(
+                "No accounts owned by {} existed before the migration, so there were none to \
+                 check. Load some at genesis with `accounts`, `account_dir`, `cloned_accounts` or \
+                 `staged_accounts` to check the migration preserves them.",
+                program_id
+            ),
+            Color::Yellow,
+        );
+        return;
+    }
+    if diffs.is_empty() {
+        boomerang(
+            &format!(
+                "All {} accounts owned by {} were preserved by the migration",
+                num_accounts, program_id
+            ),
+            Color::Green,
+        );
+        return;
+    }
+    let color = if diffs.iter().all(AccountDiff::is_expected) {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    boomerang(
+        &format!(
+            "The migration changed {} of the accounts owned by {}",
+            diffs.len(),
+            program_id
+        ),
+        color,
+    );
+    account_snapshot_diffs_output(diffs).unwrap();
+}
@@ -0,0 +1,230 @@
+use {
+    crate::{dirs, validator_options::IntoTestValidatorStartOptions},
+    solana_boomerang_client::{fixtures::write_account_fixture, BoomerangTestClientConfig},
+    solana_boomerang_test_validator::start_options::BoomerangTestValidatorStartOptions,
+    std::{io, path::PathBuf},
+};
+
+/// The first port handed out to pooled test validators.
+/// Chosen to stay clear of the `solana-test-validator` defaults (8899, 9900).
+const POOL_BASE_PORT: u16 = 10_000;
+/// The number of ports reserved for each pooled test validator
+const POOL_PORT_BLOCK_SIZE: u16 = 100;
+/// The offset within a port block where the validator's dynamic port range
+/// begins
+const POOL_DYNAMIC_PORT_OFFSET: u16 = 10;
+
+/// The maximum number of test validators that can be pooled before running
+/// out of port blocks
+pub const MAX_POOL_SIZE: usize = ((u16::MAX - POOL_BASE_PORT) / POOL_PORT_BLOCK_SIZE) as usize;
+
+/// The ports and ledger assigned to one slot of the test validator pool.
+///
+/// Each slot owns a disjoint block of ports, so validators in different slots
+/// can run side by side without contending for the RPC, faucet, gossip or
+/// dynamic ports.
+pub struct ValidatorPoolSlot {
+    ledger_path: PathBuf,
+    staged_accounts_path: PathBuf,
+    ports: Option<ValidatorPorts>,
+}
+
+struct ValidatorPorts {
+    rpc: u16,
+    faucet: u16,
+    gossip: u16,
+    dynamic_range: (u16, u16),
+}
+impl ValidatorPorts {
+    fn for_slot(slot: usize) -> Self {
+        assert!(
+            slot < MAX_POOL_SIZE,
+            "Test validator pool slot {} exceeds the maximum pool size of {}",
+            slot,
+            MAX_POOL_SIZE
+        );
+        let base = POOL_BASE_PORT + slot as u16 * POOL_PORT_BLOCK_SIZE;
+        Self {
+            // The RPC websocket is served on `rpc + 1`
+            rpc: base,
+            faucet: base + 2,
+            gossip: base + 3,
+            dynamic_range: (
+                base + POOL_DYNAMIC_PORT_OFFSET,
+                base + POOL_PORT_BLOCK_SIZE - 1,
+            ),
+        }
+    }
+}
+
+impl ValidatorPoolSlot {
+    /// The single slot used when integration tests are run serially, which
+    /// keeps the `solana-test-validator` default ports and ledger
+    pub fn serial() -> Self {
+        let ledger_path = dirs::test_ledger_path();
+        Self {
+            staged_accounts_path: dirs::staged_accounts_path(&ledger_path),
+            ledger_path,
+            ports: None,
+        }
+    }
+
+    pub fn pooled(slot: usize) -> Self {
+        let ports = ValidatorPorts::for_slot(slot);
+        let ledger_path = dirs::pooled_test_ledger_path(slot);
+        Self {
+            staged_accounts_path: dirs::staged_accounts_path(&ledger_path),
+            ledger_path,
+            ports: Some(ports),
+        }
+    }
+
+    pub fn ledger_path(&self) -> PathBuf {
+        self.ledger_path.clone()
+    }
+
+    /// Write the config's staged accounts to the slot's staged accounts
+    /// directory as JSON account dumps, returning the options that load them
+    /// at genesis.
+    /// A slot runs one chunk at a time, so the directory only ever holds the
+    /// current chunk's dumps.
+    pub fn stage_accounts(
+        &self,
+        config: &BoomerangTestClientConfig,
+    ) -> Vec<BoomerangTestValidatorStartOptions> {
+        if config.staged_accounts.is_empty() {
+            return vec![];
+        }
+        dirs::create_directory(&self.staged_accounts_path);
+        config
+            .staged_accounts
+            .iter()
+            .map(|(address, account)| {
+                let dump_path = self.staged_accounts_path.join(format!("{}.json", address));
+                write_account_fixture(&dump_path, address, account);
+                BoomerangTestValidatorStartOptions::Account {
+                    address: address.to_string(),
+                    dump_path,
+                }
+            })
+            .collect()
+    }
+
+    /// Remove the dumps written by `stage_accounts`, once the test validator
+    /// that loaded them has been torn down
+    pub fn remove_staged_accounts(&self) {
+        match std::fs::remove_dir_all(&self.staged_accounts_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => panic!(
+                "Failed to remove staged accounts at {:?}: {}",
+                self.staged_accounts_path, err
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Panics if any of the options sets a port.
+/// Every pool slot's test validator would be started with the same port, in
+/// place of the slot's own, so they'd collide.
+pub fn assert_no_port_options(options: &[BoomerangTestValidatorStartOptions]) {
+    if let Some(option) = options.iter().find(|option| option.is_port()) {
+        panic!(
+            "`validator_start_options` can't set {:?} with a `validator_pool_size` above 1, \
+             since every pooled test validator would listen on the same port",
+            option
+        );
+    }
+}
+
+impl IntoTestValidatorStartOptions for ValidatorPoolSlot {
+    fn to_test_validator_start_options(&self) -> Vec<BoomerangTestValidatorStartOptions> {
+        match &self.ports {
+            Some(ports) => vec![
+                BoomerangTestValidatorStartOptions::RpcPort { port: ports.rpc },
+                BoomerangTestValidatorStartOptions::FaucetPort { port: ports.faucet },
+                BoomerangTestValidatorStartOptions::GossipPort { port: ports.gossip },
+                BoomerangTestValidatorStartOptions::DynamicPortRange {
+                    start: ports.dynamic_range.0,
+                    end: ports.dynamic_range.1,
+                },
+            ],
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port_block(slot: usize) -> (u16, u16) {
+        let ports = ValidatorPorts::for_slot(slot);
+        let ports = [
+            ports.rpc,
+            // The RPC websocket
+            ports.rpc + 1,
+            ports.faucet,
+            ports.gossip,
+            ports.dynamic_range.0,
+            ports.dynamic_range.1,
+        ];
+        (*ports.iter().min().unwrap(), *ports.iter().max().unwrap())
+    }
+
+    #[test]
+    fn test_pooled_port_blocks_do_not_overlap() {
+        let blocks = (0..MAX_POOL_SIZE).map(port_block).collect::<Vec<_>>();
+        for window in blocks.windows(2) {
+            let ((_, end), (next_start, _)) = (window[0], window[1]);
+            assert!(
+                end < next_start,
+                "Port block ending at {} overlaps the next block starting at {}",
+                end,
+                next_start
+            );
+        }
+    }
+
+    #[test]
+    fn test_pooled_ports_within_slot_are_distinct() {
+        let ports = ValidatorPorts::for_slot(0);
+        let (start, end) = ports.dynamic_range;
+        for port in [ports.rpc, ports.rpc + 1, ports.faucet, ports.gossip] {
+            assert!(port < start || port > end);
+        }
+        assert_eq!(
+            [ports.rpc, ports.rpc + 1, ports.faucet, ports.gossip]
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_highest_pooled_slot_stays_in_port_range() {
+        let (start, end) = port_block(MAX_POOL_SIZE - 1);
+        assert!(start >= POOL_BASE_PORT);
+        assert!(end < u16::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the maximum pool size")]
+    fn test_pooled_slot_past_max_pool_size_panics() {
+        ValidatorPorts::for_slot(MAX_POOL_SIZE);
+    }
+
+    #[test]
+    fn test_no_port_options_allows_other_options() {
+        assert_no_port_options(&[
+            BoomerangTestValidatorStartOptions::ComputeUnitLimit { units: 400_000 },
+            BoomerangTestValidatorStartOptions::WarpSlot { warp_slot: 123 },
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "every pooled test validator would listen on the same port")]
+    fn test_no_port_options_rejects_a_port() {
+        assert_no_port_options(&[BoomerangTestValidatorStartOptions::RpcPort { port: 8899 }]);
+    }
+}
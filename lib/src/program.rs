@@ -1,6 +1,6 @@
 use {
-    crate::{output, BoomerangTest, BoomerangTests},
-    libtest_mimic::{Arguments, Trial},
+    crate::{fixtures, output, BoomerangTest, BoomerangTestFn, BoomerangTests, NativeProgram},
+    libtest_mimic::{Arguments, Conclusion, Trial},
     solana_boomerang_client::BoomerangTestClientConfig,
     solana_sdk::pubkey::Pubkey,
     std::str::FromStr,
@@ -17,13 +17,48 @@ fn setup_config_for_test(
     config.program_id = *program_id;
 }
 
+/// The outcome of a single trial
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrialOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+impl From<&Conclusion> for TrialOutcome {
+    fn from(conclusion: &Conclusion) -> Self {
+        if conclusion.num_failed > 0 {
+            Self::Failed
+        } else if conclusion.num_passed > 0 {
+            Self::Passed
+        } else {
+            Self::Ignored
+        }
+    }
+}
+
+/// Panics if the program would be loaded at the address of a runtime builtin.
+/// Neither `ProgramTest` nor the test validator can replace a builtin: the
+/// bank keeps dispatching a builtin's address to the builtin, whether the
+/// program is stored over it at genesis or added as another builtin, so the
+/// program would silently never run.
+fn assert_not_builtin_address(program_file: &str, program_id: &Pubkey) {
+    if let Some(native_program) = NativeProgram::from_program_id(program_id) {
+        panic!(
+            "Cannot load {} at {}, the address of the runtime builtin {}. Deploy it at another \
+             address and use `retarget_instruction`, or declare a migration test against {}.",
+            program_file, program_id, native_program, native_program,
+        );
+    }
+}
+
 /// A chunk of tests for a single program.
 /// These chunks are provided to the `entrypoint` as trials that share a common
 /// setup config.
 pub struct BoomerangProgramTestChunk {
     args: Arguments,
     config: BoomerangTestClientConfig,
-    trials: Vec<Trial>,
+    test_funcs: Vec<BoomerangTestFn>,
+    use_banks: bool,
 }
 impl BoomerangProgramTestChunk {
     pub fn new(
@@ -36,17 +71,21 @@ impl BoomerangProgramTestChunk {
 
         let mut config = test_config.clone();
         setup_config_for_test(&mut config, program_file, program_id);
+        fixtures::resolve_cloned_accounts(&mut config);
+        config
+            .extra_programs
+            .iter()
+            .for_each(|(program_file, program_id)| {
+                assert_not_builtin_address(program_file, program_id)
+            });
 
         let args = Arguments::default();
-        let trials = test_funcs
-            .iter()
-            .map(|test_func| test_func(config.clone(), use_banks))
-            .collect();
 
         Self {
             args,
             config,
-            trials,
+            test_funcs: test_funcs.to_vec(),
+            use_banks,
         }
     }
 
@@ -54,12 +93,49 @@ impl BoomerangProgramTestChunk {
         &self.config
     }
 
+    /// Mutable access to the chunk's shared setup config.
+    /// Since trials are only built when the chunk is run, any changes made
+    /// here (such as pointing the chunk at a particular test validator) are
+    /// seen by every trial in the chunk.
+    pub fn config_mut(&mut self) -> &mut BoomerangTestClientConfig {
+        &mut self.config
+    }
+
+    /// Build the chunk's trials from its current setup config.
+    pub fn trials(&self) -> Vec<Trial> {
+        self.test_funcs
+            .iter()
+            .map(|test_func| test_func(self.config.clone(), self.use_banks))
+            .collect()
+    }
+
+    /// Run the tests for a single chunk of program tests with shared setup
+    /// configs, returning the conclusion rather than exiting on failure.
+    pub fn run_without_exit(&self) -> Conclusion {
+        libtest_mimic::run(&self.args, self.trials())
+    }
+
+    /// Run each trial in the chunk on its own, returning every trial's name
+    /// and outcome in order.
+    /// Useful when the outcomes of individual trials need to be compared
+    /// across runs, rather than just the chunk's overall conclusion.
+    pub fn run_each(&self) -> Vec<(String, TrialOutcome)> {
+        self.trials()
+            .into_iter()
+            .map(|trial| {
+                let name = trial.name().to_string();
+                let conclusion = libtest_mimic::run(&self.args, vec![trial]);
+                (name, TrialOutcome::from(&conclusion))
+            })
+            .collect()
+    }
+
     /// Run the tests for a single chunk of program tests with shared setup
     /// configs.
     /// This particular function is used for integration and migration tests,
     /// since it allows each chunk to be run serially.
     pub fn run(self) {
-        libtest_mimic::run(&self.args, self.trials).exit_if_failed();
+        self.run_without_exit().exit_if_failed();
     }
 }
 
@@ -75,6 +151,8 @@ impl BoomerangProgramTestIteration {
         let program_file = file.to_string();
         let program_id = Pubkey::from_str(id).unwrap();
 
+        assert_not_builtin_address(&program_file, &program_id);
+
         let chunks = tests
             .iter()
             .map(|test_suite| {
@@ -96,13 +174,20 @@ impl BoomerangProgramTestIteration {
         &self.program_file
     }
 
+    /// Apply `configure` to the setup config of every chunk in the iteration
+    pub fn configure(&mut self, configure: impl Fn(&mut BoomerangTestClientConfig)) {
+        self.chunks
+            .iter_mut()
+            .for_each(|chunk| configure(chunk.config_mut()));
+    }
+
     /// Run the tests for the entire iteration in parallel.
     pub fn parallel_run(self) {
         let args = Arguments::default();
         let trials = self
             .chunks
             .into_iter()
-            .flat_map(|chunk| chunk.trials)
+            .flat_map(|chunk| chunk.trials())
             .collect();
         libtest_mimic::run(&args, trials).exit_if_failed();
     }
@@ -0,0 +1,66 @@
+use {
+    crate::{
+        dirs,
+        integration::BoomerangIntegrationTest,
+        output,
+        program::{BoomerangProgramTest, BoomerangProgramTestIteration},
+        BoomerangTests,
+    },
+    solana_boomerang_client::recording::RecordingMode,
+};
+
+/// The Replay Test runner.
+/// Records every trial as a program test, then replays each recording
+/// against a test validator in place of the trial, failing if any
+/// transaction's result or account state differs from what was recorded.
+pub struct BoomerangReplayTest {
+    recordings: Vec<BoomerangProgramTestIteration>,
+    replays: BoomerangIntegrationTest,
+}
+impl BoomerangReplayTest {
+    pub fn new(
+        programs: &[(&str, &str)],
+        tests: BoomerangTests<'_>,
+        validator_pool_size: usize,
+    ) -> Self {
+        let mut recordings = BoomerangProgramTest::build_program_test_iterations(
+            programs, tests, /* use_banks */ true,
+        );
+        recordings.iter_mut().for_each(|iteration| {
+            let directory = dirs::recording_directory_path(iteration.program_file());
+            iteration.configure(|config| {
+                config.recording = Some(RecordingMode::Record(directory.clone()));
+            });
+        });
+
+        let mut replays = BoomerangIntegrationTest::new(programs, tests, validator_pool_size);
+        replays.configure(|program_file, config| {
+            config.recording = Some(RecordingMode::Replay(dirs::recording_directory_path(
+                program_file,
+            )));
+        });
+
+        Self {
+            recordings,
+            replays,
+        }
+    }
+
+    pub fn run(self) {
+        for iteration in self.recordings {
+            output::recording_program_tests(iteration.program_file());
+
+            // Clear out recordings of trials that no longer exist
+            let directory = dirs::recording_directory_path(iteration.program_file());
+            if directory.exists() {
+                std::fs::remove_dir_all(&directory).expect("Failed to clear recording directory");
+            }
+            dirs::create_directory(&directory);
+
+            iteration.parallel_run();
+        }
+
+        output::replaying_recordings();
+        self.replays.run();
+    }
+}
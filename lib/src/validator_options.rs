@@ -6,6 +6,10 @@ use {
     },
 };
 
+/// Converts a setup config into the options to start a test validator with.
+/// The conversion doesn't touch the filesystem, so options that need files
+/// written first, such as staged accounts, are left to the runner starting
+/// the validator.
 pub trait IntoTestValidatorStartOptions {
     fn to_test_validator_start_options(&self) -> Vec<BoomerangTestValidatorStartOptions>;
 }
@@ -20,12 +24,40 @@ impl IntoTestValidatorStartOptions for BoomerangTestClientConfig {
             upgrade_authority: AddressOrKeypair::Address(self.program_id.to_string()),
         });
 
+        // Extra programs are loaded with the non-upgradeable loader, matching
+        // how `ProgramTest` loads them for program tests
+        self.extra_programs
+            .iter()
+            .for_each(|(program_file, program_id)| {
+                options.push(BoomerangTestValidatorStartOptions::BpfProgram {
+                    address_or_keypair: AddressOrKeypair::Address(program_id.to_string()),
+                    so_file_path: dirs::program_so_path(program_file),
+                });
+            });
+
+        if let Some(account_dir) = &self.account_dir {
+            options.push(BoomerangTestValidatorStartOptions::AccountDir {
+                directory: account_dir.clone(),
+            });
+        }
+
+        self.accounts.iter().for_each(|(address, dump_path)| {
+            options.push(BoomerangTestValidatorStartOptions::Account {
+                address: address.to_string(),
+                dump_path: dump_path.clone(),
+            });
+        });
+
         self.features_disabled.iter().for_each(|feature| {
             options.push(BoomerangTestValidatorStartOptions::DeactivateFeature {
                 feature_pubkey: feature.to_string(),
             });
         });
 
+        if let Some(slots) = self.slots_per_epoch {
+            options.push(BoomerangTestValidatorStartOptions::SlotsPerEpoch { slots });
+        }
+
         if self.warp_slot > 0 {
             options.push(BoomerangTestValidatorStartOptions::WarpSlot {
                 warp_slot: self.warp_slot,
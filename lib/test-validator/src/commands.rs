@@ -1,50 +1,230 @@
 use std::{
+    ffi::OsStr,
+    fmt,
+    fs::File,
+    io::{self, Read, Write},
     path::Path,
-    process::{Command, Stdio},
+    process::{Child, Command, ExitStatus, Stdio},
 };
 
-pub fn run_command(command: &str) {
-    let status = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .status()
-        .expect("failed to execute process");
-    assert!(status.success());
+/// An error from running a command
+#[derive(Debug)]
+pub enum CommandError {
+    /// The command could not be spawned, e.g. because the program was not
+    /// found
+    Spawn { command: String, source: io::Error },
+    /// The command ran and exited unsuccessfully
+    Failed {
+        command: String,
+        status: ExitStatus,
+        /// Everything the command wrote to stderr
+        stderr: String,
+    },
+}
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn { command, source } => {
+                write!(f, "Failed to execute {}: {}", command, source)
+            }
+            Self::Failed {
+                command,
+                status,
+                stderr,
+            } => write!(f, "{} exited with {}\nstderr:\n{}", command, status, stderr),
+        }
+    }
+}
+impl std::error::Error for CommandError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Spawn { source, .. } => Some(source),
+            Self::Failed { .. } => None,
+        }
+    }
 }
 
-pub fn run_command_detached(command: &str) {
-    Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .stdout(Stdio::null())
-        .spawn()
-        .expect("failed to execute process");
+/// The program is executed directly rather than through a shell, and each
+/// argument is passed to it as is, so nothing is split, quoted or expanded
+fn new_command(
+    program: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+) -> Command {
+    let mut command = Command::new(program);
+    command.args(args);
+    command
 }
 
-pub fn run_command_with_dir(command: &str, dir: &Path) {
-    let status = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .current_dir(dir)
-        .status()
-        .expect("failed to execute process");
-    assert!(status.success());
+/// The command line, with each argument quoted, for error messages
+fn command_line(command: &Command) -> String {
+    format!("{:?}", command)
 }
 
-pub fn run_command_with_num_retries(command: &str, num_retries: u8) {
-    let mut retries = 0;
+fn spawn(command: &mut Command) -> Result<Child, CommandError> {
+    command.spawn().map_err(|source| CommandError::Spawn {
+        command: command_line(command),
+        source,
+    })
+}
+
+/// Runs the command to completion, passing its stderr through while also
+/// capturing it for the error if the command fails
+fn run(mut command: Command) -> Result<(), CommandError> {
+    let mut child = spawn(command.stderr(Stdio::piped()))?;
+
+    let mut stderr = Vec::new();
+    let mut child_stderr = child.stderr.take().unwrap();
+    let mut buffer = [0; 4096];
     loop {
-        let status = Command::new("sh")
-            .arg("-c")
-            .arg(command)
-            .status()
-            .expect("failed to execute process");
-        if status.success() {
-            break;
+        match child_stderr.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => {
+                let _ = io::stderr().write_all(&buffer[..len]);
+                stderr.extend_from_slice(&buffer[..len]);
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
         }
-        retries += 1;
-        if retries > num_retries {
-            panic!("failed to execute process");
+    }
+
+    let status = child.wait().map_err(|source| CommandError::Spawn {
+        command: command_line(&command),
+        source,
+    })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(CommandError::Failed {
+            command: command_line(&command),
+            status,
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        })
+    }
+}
+
+pub fn run_command(
+    program: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+) -> Result<(), CommandError> {
+    run(new_command(program, args))
+}
+
+/// Spawns the command without waiting for it to exit, redirecting both stdout
+/// and stderr to the file at `log_path`.
+pub fn run_command_detached(
+    program: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    log_path: &Path,
+) -> Result<Child, CommandError> {
+    let mut command = new_command(program, args);
+    let stdout = File::create(log_path).map_err(|source| CommandError::Spawn {
+        command: command_line(&command),
+        source,
+    })?;
+    let stderr = stdout.try_clone().map_err(|source| CommandError::Spawn {
+        command: command_line(&command),
+        source,
+    })?;
+    spawn(command.stdin(Stdio::null()).stdout(stdout).stderr(stderr))
+}
+
+pub fn run_command_with_dir(
+    program: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    dir: &Path,
+) -> Result<(), CommandError> {
+    let mut command = new_command(program, args);
+    command.current_dir(dir);
+    run(command)
+}
+
+/// Runs the command, retrying up to `num_retries` times if it fails.
+/// Returns the last failure if every attempt fails.
+pub fn run_command_with_num_retries(
+    program: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    num_retries: u8,
+) -> Result<(), CommandError> {
+    let args = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_owned())
+        .collect::<Vec<_>>();
+    let mut retries = 0;
+    loop {
+        match run_command(program.as_ref(), &args) {
+            Ok(()) => return Ok(()),
+            Err(err) if retries >= num_retries => return Err(err),
+            Err(_) => retries += 1,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_command_reports_command_line_status_and_stderr() {
+        let err = run_command("sh", ["-c", "echo 'something broke' >&2; exit 3"]).unwrap_err();
+        let CommandError::Failed {
+            command,
+            status,
+            stderr,
+        } = &err
+        else {
+            panic!("Expected the command to fail, got {:?}", err);
+        };
+        assert!(command.contains("\"sh\""), "{}", command);
+        assert!(command.contains("exit 3"), "{}", command);
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(stderr, "something broke\n");
+
+        let message = err.to_string();
+        assert!(message.contains(command.as_str()), "{}", message);
+        assert!(message.contains(&status.to_string()), "{}", message);
+        assert!(message.contains("something broke"), "{}", message);
+    }
+
+    #[test]
+    fn test_missing_program_is_a_spawn_error() {
+        let err = run_command("boomerang-no-such-program", ["--version"]).unwrap_err();
+        assert!(
+            matches!(&err, CommandError::Spawn { command, source }
+                if command.contains("boomerang-no-such-program")
+                    && source.kind() == io::ErrorKind::NotFound),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_arguments_are_passed_without_splitting_or_expansion() {
+        let arg = "a path/with spaces, 'single' and \"double\" quotes, $HOME and ~";
+        let output_path =
+            std::env::temp_dir().join(format!("boomerang-command-args-{}", std::process::id()));
+        run_command(
+            "sh",
+            [
+                OsStr::new("-c"),
+                OsStr::new(r#"[ "$#" -eq 2 ] && printf '%s' "$1" > "$2""#),
+                OsStr::new("sh"),
+                OsStr::new(arg),
+                output_path.as_os_str(),
+            ],
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+        let received = std::fs::read_to_string(&output_path).unwrap();
+        std::fs::remove_file(&output_path).unwrap();
+        assert_eq!(received, arg);
+    }
+
+    #[test]
+    fn test_retries_return_the_last_failure() {
+        let err = run_command_with_num_retries("sh", ["-c", "exit 7"], 2).unwrap_err();
+        assert!(
+            matches!(&err, CommandError::Failed { status, .. } if status.code() == Some(7)),
+            "{:?}",
+            err
+        );
+    }
+}
@@ -2,69 +2,217 @@ pub mod commands;
 pub mod start_options;
 
 use {
-    commands::{run_command, run_command_detached, run_command_with_num_retries},
+    commands::{run_command, run_command_detached, CommandError},
+    solana_client::rpc_client::RpcClient,
     start_options::BoomerangTestValidatorStartOptions,
-    std::path::PathBuf,
+    std::{
+        ffi::OsString,
+        io,
+        path::{Path, PathBuf},
+        process::Child,
+        time::{Duration, Instant},
+    },
 };
 
+/// The RPC endpoint `solana-test-validator` listens on when no `--rpc-port` is
+/// provided
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const READINESS_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub struct BoomerangTestValidator {
     ledger_path: PathBuf,
+    log_path: PathBuf,
+    process: Option<Child>,
+    rpc_url: String,
     solana_cli_alias: String,
     solana_test_validator_alias: String,
-    test_validator_start_options: String,
+    startup_timeout: Duration,
+    test_validator_start_options: Vec<OsString>,
 }
 impl BoomerangTestValidator {
+    /// Set up a test validator started with the given groups of options.
+    /// A single-valued option in a later group replaces the same option in an
+    /// earlier one, so callers can let user options override derived ones.
+    ///
+    /// The CLI and test validator aliases are executed directly rather than
+    /// through a shell, so each must be a program on `PATH` or a path to one.
+    /// Shell aliases and `~` aren't expanded.
     pub fn new(
         ledger_path: PathBuf,
         solana_cli_alias: &str,
         solana_test_validator_alias: &str,
         start_options: &[&[BoomerangTestValidatorStartOptions]],
     ) -> Self {
-        let mut test_validator_start_options = String::new();
+        let start_options = BoomerangTestValidatorStartOptions::merge(start_options);
+        let rpc_url = start_options
+            .iter()
+            .find_map(|option| match option {
+                BoomerangTestValidatorStartOptions::RpcPort { port } => {
+                    Some(format!("http://127.0.0.1:{}", port))
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| DEFAULT_RPC_URL.to_string());
 
-        start_options.iter().for_each(|options| {
-            test_validator_start_options.push_str(&format!(
-                " {}",
-                BoomerangTestValidatorStartOptions::args_to_string(options).as_str()
-            ));
-        });
+        let mut test_validator_start_options =
+            BoomerangTestValidatorStartOptions::args(&start_options);
 
-        test_validator_start_options
-            .push_str(format!(" --ledger {}", ledger_path.to_str().unwrap()).as_str());
+        test_validator_start_options.push("--ledger".into());
+        test_validator_start_options.push(ledger_path.clone().into_os_string());
+
+        // The ledger is wiped on teardown, so the captured output lives next
+        // to it instead of inside it
+        let log_path = ledger_path.with_extension("log");
 
         Self {
             ledger_path,
+            log_path,
+            process: None,
+            rpc_url,
             solana_cli_alias: solana_cli_alias.to_string(),
             solana_test_validator_alias: solana_test_validator_alias.to_string(),
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
             test_validator_start_options,
         }
     }
 
-    pub fn solana_feature_activate(&self, feature_keypair_path: &str) {
-        println!("Activating feature: {}", feature_keypair_path);
-        let command = format!(
-            "{} feature activate {} development",
-            self.solana_cli_alias, feature_keypair_path,
-        );
-        run_command(&command)
+    /// Set the RPC endpoint used to probe the validator's readiness.
+    /// Defaults to the endpoint of the `--rpc-port` the validator is started
+    /// with, so this is only needed to reach it some other way.
+    pub fn with_rpc_url(mut self, rpc_url: &str) -> Self {
+        self.rpc_url = rpc_url.to_string();
+        self
+    }
+
+    /// Set how long to wait for the validator to become healthy before
+    /// giving up on startup
+    pub fn with_startup_timeout(mut self, startup_timeout: Duration) -> Self {
+        self.startup_timeout = startup_timeout;
+        self
+    }
+
+    /// The file capturing the validator's stdout and stderr
+    pub fn log_path(&self) -> &PathBuf {
+        &self.log_path
     }
 
-    pub fn solana_test_validator_start(&self) {
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    pub fn solana_feature_activate(&self, feature_keypair_path: &Path) -> Result<(), CommandError> {
+        println!("Activating feature: {:?}", feature_keypair_path);
+        run_command(
+            &self.solana_cli_alias,
+            [
+                OsString::from("feature"),
+                "activate".into(),
+                feature_keypair_path.into(),
+                "development".into(),
+            ],
+        )
+    }
+
+    /// Spawn the test validator and block until it reports healthy over RPC
+    /// and has produced its first slot.
+    ///
+    /// Panics if another node is already serving the RPC endpoint, if the
+    /// validator exits during startup, or if it does not become ready within
+    /// the startup timeout.
+    pub fn solana_test_validator_start(&mut self) {
         println!("Starting test validator");
         println!("Ledger path: {:?}", self.ledger_path);
-        let command = format!(
-            "{} {}",
-            self.solana_test_validator_alias, self.test_validator_start_options,
-        );
-        run_command_detached(&command);
-        std::thread::sleep(std::time::Duration::from_secs(5));
+        println!("Log path: {:?}", self.log_path);
+
+        let rpc_client =
+            RpcClient::new_with_timeout(self.rpc_url.clone(), READINESS_REQUEST_TIMEOUT);
+        if rpc_client.get_health().is_ok() {
+            panic!(
+                "A validator is already serving {}. Stop it before running Boomerang tests.",
+                self.rpc_url
+            );
+        }
+
+        let process = run_command_detached(
+            &self.solana_test_validator_alias,
+            &self.test_validator_start_options,
+            &self.log_path,
+        )
+        .unwrap_or_else(|err| panic!("Failed to start test validator: {}", err));
+        self.process = Some(process);
+
+        let deadline = Instant::now() + self.startup_timeout;
+        loop {
+            if let Some(status) = self.process.as_mut().unwrap().try_wait().unwrap() {
+                self.process = None;
+                panic!(
+                    "Test validator exited during startup with {}. See logs at {:?}",
+                    status, self.log_path
+                );
+            }
+            if rpc_client.get_health().is_ok() && rpc_client.get_slot().is_ok_and(|slot| slot > 0) {
+                return;
+            }
+            if Instant::now() >= deadline {
+                self.solana_test_validator_stop();
+                panic!(
+                    "Test validator did not become ready within {:?}. See logs at {:?}",
+                    self.startup_timeout, self.log_path
+                );
+            }
+            std::thread::sleep(READINESS_POLL_INTERVAL);
+        }
     }
 
-    pub fn solana_test_validator_teardown(&self) {
+    /// Stop the test validator process, if one is running.
+    /// The process is sent `SIGTERM` and given a grace period to exit before
+    /// being sent `SIGKILL`.
+    pub fn solana_test_validator_stop(&mut self) {
+        let Some(mut process) = self.process.take() else {
+            return;
+        };
+        println!("Stopping test validator");
+
+        // SAFETY: `kill` has no memory safety requirements, and the pid belongs
+        // to a child process we have not yet reaped
+        unsafe {
+            libc::kill(process.id() as libc::pid_t, libc::SIGTERM);
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_GRACE_PERIOD;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = process.try_wait() {
+                return;
+            }
+            std::thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        println!("Test validator did not exit after SIGTERM, sending SIGKILL");
+        let _ = process.kill();
+        let _ = process.wait();
+    }
+
+    /// Stop the test validator process and remove its ledger
+    pub fn solana_test_validator_teardown(&mut self) {
+        self.solana_test_validator_stop();
         println!("Tearing down test validator");
         println!("Ledger path: {:?}", self.ledger_path);
-        let command = format!("rm -rf {}", self.ledger_path.to_str().unwrap());
-        run_command_with_num_retries(&command, 3);
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        match std::fs::remove_dir_all(&self.ledger_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                panic!("Failed to remove ledger: {}", err)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for BoomerangTestValidator {
+    fn drop(&mut self) {
+        self.solana_test_validator_stop();
     }
 }
@@ -1,30 +1,30 @@
-use std::path::PathBuf;
+use std::{ffi::OsString, mem, path::PathBuf};
 
-trait ToStringArg {
-    fn to_string_arg(&self) -> String;
+trait ToArg {
+    fn to_arg(&self) -> OsString;
 }
 
-impl ToStringArg for PathBuf {
-    fn to_string_arg(&self) -> String {
-        self.to_str()
-            .expect("Failed to convert path to string")
-            .to_string()
+impl ToArg for PathBuf {
+    fn to_arg(&self) -> OsString {
+        self.clone().into_os_string()
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum AddressOrKeypair {
     Address(String),
     Keypair(PathBuf),
 }
-impl ToStringArg for AddressOrKeypair {
-    fn to_string_arg(&self) -> String {
+impl ToArg for AddressOrKeypair {
+    fn to_arg(&self) -> OsString {
         match self {
-            AddressOrKeypair::Address(address) => address.to_string(),
-            AddressOrKeypair::Keypair(keypair_path) => keypair_path.to_string_arg(),
+            AddressOrKeypair::Address(address) => address.into(),
+            AddressOrKeypair::Keypair(keypair_path) => keypair_path.to_arg(),
         }
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum UrlOrMoniker {
     Url(String),
     Localnet,
@@ -33,12 +33,24 @@ pub enum UrlOrMoniker {
     MainnetBeta,
 }
 
+/// The loader a program is deployed with at genesis
+#[derive(Clone, Debug)]
+pub enum ProgramLoader {
+    /// The non-upgradeable BPF loader, as with `--bpf-program`
+    NonUpgradeable,
+    /// The upgradeable BPF loader, as with `--upgradeable-program`
+    Upgradeable { upgrade_authority: AddressOrKeypair },
+}
+
+#[derive(Clone, Debug)]
 pub enum BoomerangTestValidatorStartOptions {
     /// Load an account from the provided JSON file
     Account { address: String, dump_path: PathBuf },
     /// Load all the accounts from the JSON files found in the specified
     /// DIRECTORY
     AccountDir { directory: PathBuf },
+    /// IP address to bind the validator ports to
+    BindAddress { address: String },
     /// Add a SBF program to the genesis configuration with upgrades disabled
     BpfProgram {
         address_or_keypair: AddressOrKeypair,
@@ -50,17 +62,39 @@ pub enum BoomerangTestValidatorStartOptions {
     /// Copy an upgradeable program and its executable data from the cluster
     /// referenced by the --url argument the genesis configuration
     CloneUpgradeableProgram { address: String },
+    /// Copy the feature set from the cluster referenced by the --url argument
+    /// into the genesis configuration
+    CloneFeatureSet,
+    /// Override the runtime's compute unit limit per transaction
+    ComputeUnitLimit { units: u64 },
     /// Configuration file to use
     Config { path: PathBuf },
     /// Deactivate this feature in genesis
     DeactivateFeature { feature_pubkey: String },
+    /// Range to use for dynamically assigned ports
+    DynamicPortRange { start: u16, end: u16 },
+    /// Enable a faucet on this port
+    FaucetPort { port: u16 },
+    /// Gossip port number for the validator
+    GossipPort { port: u16 },
+    /// Specify the configuration file for a Geyser plugin
+    GeyserPluginConfig { path: PathBuf },
+    /// Keep this amount of shreds in root slots, or the validator's default
+    /// if no amount is provided
+    LimitLedgerSize { shreds: Option<u64> },
+    /// Stream the validator's log instead of showing its dashboard
+    Log,
     /// Copy an account from the cluster referenced by the --url argument,
     /// skipping it if it doesn't exist
     MaybeClone { address: String },
     /// Address of the mint account that will receive tokens created at genesis
     Mint { address: String },
+    /// Enable JSON RPC on this port, and the next port for the RPC websocket
+    RpcPort { port: u16 },
     /// Override the number of slots in an epoch
     SlotsPerEpoch { slots: u64 },
+    /// The number of ticks in a slot
+    TicksPerSlot { ticks: u64 },
     /// Add an upgradeable SBF program to the genesis configuration
     UpgradeableProgram {
         address_or_keypair: AddressOrKeypair,
@@ -74,72 +108,482 @@ pub enum BoomerangTestValidatorStartOptions {
     /// by the --url argument will be used.
     WarpSlot { warp_slot: u64 },
 }
-impl ToStringArg for BoomerangTestValidatorStartOptions {
-    fn to_string_arg(&self) -> String {
+impl BoomerangTestValidatorStartOptions {
+    /// The option as `solana-test-validator` arguments
+    pub fn to_args(&self) -> Vec<OsString> {
         match self {
             Self::Account { address, dump_path } => {
-                format!("--account {} {}", address, dump_path.to_string_arg())
-            }
-            Self::AccountDir { directory } => {
-                format!("--account-dir {}", directory.to_string_arg())
+                vec!["--account".into(), address.into(), dump_path.to_arg()]
             }
+            Self::AccountDir { directory } => vec!["--account-dir".into(), directory.to_arg()],
+            Self::BindAddress { address } => vec!["--bind-address".into(), address.into()],
             Self::BpfProgram {
                 address_or_keypair,
                 so_file_path,
-            } => format!(
-                "--bpf-program {} {}",
-                address_or_keypair.to_string_arg(),
-                so_file_path.to_string_arg()
-            ),
-            Self::Clone { address } => {
-                format!("--clone {}", address)
-            }
+            } => vec![
+                "--bpf-program".into(),
+                address_or_keypair.to_arg(),
+                so_file_path.to_arg(),
+            ],
+            Self::Clone { address } => vec!["--clone".into(), address.into()],
             Self::CloneUpgradeableProgram { address } => {
-                format!("--clone-upgradeable-program {}", address)
+                vec!["--clone-upgradeable-program".into(), address.into()]
             }
-            Self::Config { path } => {
-                format!("--config {}", path.to_string_arg())
+            Self::CloneFeatureSet => vec!["--clone-feature-set".into()],
+            Self::ComputeUnitLimit { units } => {
+                vec!["--compute-unit-limit".into(), units.to_string().into()]
             }
+            Self::Config { path } => vec!["--config".into(), path.to_arg()],
             Self::DeactivateFeature { feature_pubkey } => {
-                format!("--deactivate-feature {}", feature_pubkey)
-            }
-            Self::MaybeClone { address } => {
-                format!("--maybe-clone {}", address)
+                vec!["--deactivate-feature".into(), feature_pubkey.into()]
             }
-            Self::Mint { address } => {
-                format!("--mint {}", address)
+            Self::DynamicPortRange { start, end } => vec![
+                "--dynamic-port-range".into(),
+                format!("{}-{}", start, end).into(),
+            ],
+            Self::FaucetPort { port } => vec!["--faucet-port".into(), port.to_string().into()],
+            Self::GossipPort { port } => vec!["--gossip-port".into(), port.to_string().into()],
+            Self::GeyserPluginConfig { path } => {
+                vec!["--geyser-plugin-config".into(), path.to_arg()]
             }
+            Self::LimitLedgerSize { shreds } => match shreds {
+                Some(shreds) => vec!["--limit-ledger-size".into(), shreds.to_string().into()],
+                None => vec!["--limit-ledger-size".into()],
+            },
+            Self::Log => vec!["--log".into()],
+            Self::MaybeClone { address } => vec!["--maybe-clone".into(), address.into()],
+            Self::Mint { address } => vec!["--mint".into(), address.into()],
+            Self::RpcPort { port } => vec!["--rpc-port".into(), port.to_string().into()],
             Self::SlotsPerEpoch { slots } => {
-                format!("--slots-per-epoch {}", slots)
+                vec!["--slots-per-epoch".into(), slots.to_string().into()]
+            }
+            Self::TicksPerSlot { ticks } => {
+                vec!["--ticks-per-slot".into(), ticks.to_string().into()]
             }
             Self::UpgradeableProgram {
                 address_or_keypair,
                 so_file_path,
                 upgrade_authority,
-            } => format!(
-                "--upgradeable-program {} {} {}",
-                address_or_keypair.to_string_arg(),
-                so_file_path.to_string_arg(),
-                upgrade_authority.to_string_arg()
-            ),
+            } => vec![
+                "--upgradeable-program".into(),
+                address_or_keypair.to_arg(),
+                so_file_path.to_arg(),
+                upgrade_authority.to_arg(),
+            ],
             Self::Url { url_or_moniker } => match url_or_moniker {
-                UrlOrMoniker::Url(url) => format!("--url {}", url),
-                UrlOrMoniker::Localnet => "--ul".to_string(),
-                UrlOrMoniker::Devnet => "-ud".to_string(),
-                UrlOrMoniker::Testnet => "-ut".to_string(),
-                UrlOrMoniker::MainnetBeta => "-um".to_string(),
+                UrlOrMoniker::Url(url) => vec!["--url".into(), url.into()],
+                UrlOrMoniker::Localnet => vec!["-ul".into()],
+                UrlOrMoniker::Devnet => vec!["-ud".into()],
+                UrlOrMoniker::Testnet => vec!["-ut".into()],
+                UrlOrMoniker::MainnetBeta => vec!["-um".into()],
             },
             Self::WarpSlot { warp_slot } => {
-                format!("--warp-slot {}", warp_slot)
+                vec!["--warp-slot".into(), warp_slot.to_string().into()]
+            }
+        }
+    }
+
+    /// The options as `solana-test-validator` arguments, in order
+    pub fn args(options: &[Self]) -> Vec<OsString> {
+        options.iter().flat_map(Self::to_args).collect()
+    }
+
+    /// Whether `solana-test-validator` accepts the option's flag at most
+    /// once, rather than once per account, program or feature
+    pub fn is_single_valued(&self) -> bool {
+        matches!(
+            self,
+            Self::BindAddress { .. }
+                | Self::CloneFeatureSet
+                | Self::ComputeUnitLimit { .. }
+                | Self::Config { .. }
+                | Self::DynamicPortRange { .. }
+                | Self::FaucetPort { .. }
+                | Self::GossipPort { .. }
+                | Self::LimitLedgerSize { .. }
+                | Self::Log
+                | Self::Mint { .. }
+                | Self::RpcPort { .. }
+                | Self::SlotsPerEpoch { .. }
+                | Self::TicksPerSlot { .. }
+                | Self::Url { .. }
+                | Self::WarpSlot { .. }
+        )
+    }
+
+    /// Whether the option sets one of the ports the test validator listens
+    /// on
+    pub fn is_port(&self) -> bool {
+        matches!(
+            self,
+            Self::DynamicPortRange { .. }
+                | Self::FaucetPort { .. }
+                | Self::GossipPort { .. }
+                | Self::RpcPort { .. }
+        )
+    }
+
+    /// Merge groups of options in order, with each single-valued option
+    /// replacing any earlier option of the same variant, so that later groups
+    /// override earlier ones rather than repeating their flags
+    pub fn merge(groups: &[&[Self]]) -> Vec<Self> {
+        let mut merged: Vec<Self> = vec![];
+        for option in groups.iter().flat_map(|options| options.iter()) {
+            if option.is_single_valued() {
+                merged.retain(|merged| mem::discriminant(merged) != mem::discriminant(option));
             }
+            merged.push(option.clone());
         }
+        merged
     }
 }
-impl BoomerangTestValidatorStartOptions {
-    pub fn args_to_string(args: &[Self]) -> String {
-        args.iter()
-            .map(|arg| arg.to_string_arg())
-            .collect::<Vec<String>>()
-            .join(" ")
+
+/// Builds a list of start options, with a method for each
+/// `solana-test-validator` flag
+#[derive(Clone, Debug, Default)]
+pub struct BoomerangTestValidatorStartOptionsBuilder {
+    options: Vec<BoomerangTestValidatorStartOptions>,
+}
+impl BoomerangTestValidatorStartOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add any start option
+    pub fn option(mut self, option: BoomerangTestValidatorStartOptions) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    pub fn account(self, address: impl ToString, dump_path: impl Into<PathBuf>) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Account {
+            address: address.to_string(),
+            dump_path: dump_path.into(),
+        })
+    }
+
+    pub fn account_dir(self, directory: impl Into<PathBuf>) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::AccountDir {
+            directory: directory.into(),
+        })
+    }
+
+    pub fn bind_address(self, address: impl ToString) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::BindAddress {
+            address: address.to_string(),
+        })
+    }
+
+    /// Add a SBF program to the genesis configuration, deployed with the
+    /// given loader
+    pub fn program(
+        self,
+        address_or_keypair: AddressOrKeypair,
+        so_file_path: impl Into<PathBuf>,
+        loader: ProgramLoader,
+    ) -> Self {
+        let so_file_path = so_file_path.into();
+        self.option(match loader {
+            ProgramLoader::NonUpgradeable => BoomerangTestValidatorStartOptions::BpfProgram {
+                address_or_keypair,
+                so_file_path,
+            },
+            ProgramLoader::Upgradeable { upgrade_authority } => {
+                BoomerangTestValidatorStartOptions::UpgradeableProgram {
+                    address_or_keypair,
+                    so_file_path,
+                    upgrade_authority,
+                }
+            }
+        })
+    }
+
+    pub fn clone_account(self, address: impl ToString) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Clone {
+            address: address.to_string(),
+        })
+    }
+
+    pub fn clone_upgradeable_program(self, address: impl ToString) -> Self {
+        self.option(
+            BoomerangTestValidatorStartOptions::CloneUpgradeableProgram {
+                address: address.to_string(),
+            },
+        )
+    }
+
+    pub fn clone_feature_set(self) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::CloneFeatureSet)
+    }
+
+    pub fn compute_unit_limit(self, units: u64) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::ComputeUnitLimit { units })
+    }
+
+    pub fn config(self, path: impl Into<PathBuf>) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Config { path: path.into() })
+    }
+
+    /// Deactivate each of these features in genesis
+    pub fn deactivate_features<T: ToString>(
+        mut self,
+        feature_pubkeys: impl IntoIterator<Item = T>,
+    ) -> Self {
+        for feature_pubkey in feature_pubkeys {
+            self = self.option(BoomerangTestValidatorStartOptions::DeactivateFeature {
+                feature_pubkey: feature_pubkey.to_string(),
+            });
+        }
+        self
+    }
+
+    pub fn dynamic_port_range(self, start: u16, end: u16) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::DynamicPortRange { start, end })
+    }
+
+    pub fn faucet_port(self, port: u16) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::FaucetPort { port })
+    }
+
+    pub fn geyser_plugin_config(self, path: impl Into<PathBuf>) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::GeyserPluginConfig { path: path.into() })
+    }
+
+    pub fn gossip_port(self, port: u16) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::GossipPort { port })
+    }
+
+    pub fn limit_ledger_size(self, shreds: Option<u64>) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::LimitLedgerSize { shreds })
+    }
+
+    pub fn log(self) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Log)
+    }
+
+    pub fn maybe_clone_account(self, address: impl ToString) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::MaybeClone {
+            address: address.to_string(),
+        })
+    }
+
+    pub fn mint(self, address: impl ToString) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Mint {
+            address: address.to_string(),
+        })
+    }
+
+    pub fn rpc_port(self, port: u16) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::RpcPort { port })
+    }
+
+    pub fn slots_per_epoch(self, slots: u64) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::SlotsPerEpoch { slots })
+    }
+
+    pub fn ticks_per_slot(self, ticks: u64) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::TicksPerSlot { ticks })
+    }
+
+    pub fn url(self, url_or_moniker: UrlOrMoniker) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::Url { url_or_moniker })
+    }
+
+    pub fn warp_slot(self, warp_slot: u64) -> Self {
+        self.option(BoomerangTestValidatorStartOptions::WarpSlot { warp_slot })
+    }
+
+    pub fn build(self) -> Vec<BoomerangTestValidatorStartOptions> {
+        self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &[BoomerangTestValidatorStartOptions]) -> Vec<String> {
+        BoomerangTestValidatorStartOptions::args(options)
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_limit_ledger_size_without_shreds_is_a_bare_flag() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .limit_ledger_size(None)
+            .build();
+        assert_eq!(args(&options), ["--limit-ledger-size"]);
+    }
+
+    #[test]
+    fn test_limit_ledger_size_with_shreds() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .limit_ledger_size(Some(10_000))
+            .build();
+        assert_eq!(args(&options), ["--limit-ledger-size", "10000"]);
+    }
+
+    #[test]
+    fn test_non_upgradeable_program_loads_with_bpf_program() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .program(
+                AddressOrKeypair::Address("Program1111111111111111111111111111111111111".into()),
+                "target/deploy/program.so",
+                ProgramLoader::NonUpgradeable,
+            )
+            .build();
+        assert!(matches!(
+            options[..],
+            [BoomerangTestValidatorStartOptions::BpfProgram { .. }]
+        ));
+        assert_eq!(
+            args(&options),
+            [
+                "--bpf-program",
+                "Program1111111111111111111111111111111111111",
+                "target/deploy/program.so",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upgradeable_program_loads_with_upgradeable_program() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .program(
+                AddressOrKeypair::Keypair("program-keypair.json".into()),
+                "target/deploy/program.so",
+                ProgramLoader::Upgradeable {
+                    upgrade_authority: AddressOrKeypair::Address(
+                        "Authority1111111111111111111111111111111111".into(),
+                    ),
+                },
+            )
+            .build();
+        assert!(matches!(
+            options[..],
+            [BoomerangTestValidatorStartOptions::UpgradeableProgram { .. }]
+        ));
+        assert_eq!(
+            args(&options),
+            [
+                "--upgradeable-program",
+                "program-keypair.json",
+                "target/deploy/program.so",
+                "Authority1111111111111111111111111111111111",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deactivate_features_adds_a_flag_per_feature() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .deactivate_features(["Feature111", "Feature222"])
+            .build();
+        assert_eq!(
+            args(&options),
+            [
+                "--deactivate-feature",
+                "Feature111",
+                "--deactivate-feature",
+                "Feature222",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deactivate_no_features_adds_nothing() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .deactivate_features(Vec::<String>::new())
+            .build();
+        assert!(options.is_empty());
+    }
+
+    #[test]
+    fn test_builder_keeps_options_in_order() {
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .log()
+            .url(UrlOrMoniker::Devnet)
+            .compute_unit_limit(400_000)
+            .dynamic_port_range(10_010, 10_099)
+            .build();
+        assert_eq!(
+            args(&options),
+            [
+                "--log",
+                "-ud",
+                "--compute-unit-limit",
+                "400000",
+                "--dynamic-port-range",
+                "10010-10099",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_replaces_single_valued_options() {
+        let derived = BoomerangTestValidatorStartOptionsBuilder::new()
+            .rpc_port(10_000)
+            .warp_slot(123)
+            .build();
+        let user = BoomerangTestValidatorStartOptionsBuilder::new()
+            .rpc_port(9_000)
+            .build();
+        assert_eq!(
+            args(&BoomerangTestValidatorStartOptions::merge(&[
+                &derived, &user
+            ])),
+            ["--warp-slot", "123", "--rpc-port", "9000"]
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_repeatable_options() {
+        let derived = BoomerangTestValidatorStartOptionsBuilder::new()
+            .deactivate_features(["Feature111"])
+            .build();
+        let user = BoomerangTestValidatorStartOptionsBuilder::new()
+            .deactivate_features(["Feature222"])
+            .build();
+        assert_eq!(
+            args(&BoomerangTestValidatorStartOptions::merge(&[
+                &derived, &user
+            ])),
+            [
+                "--deactivate-feature",
+                "Feature111",
+                "--deactivate-feature",
+                "Feature222",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paths_with_spaces_and_quotes_are_single_arguments() {
+        let dump_path = "fixtures/my accounts/lookup \"table\" 'dump'.json";
+        let so_file_path = "target/deploy/it's a program.so";
+        let options = BoomerangTestValidatorStartOptionsBuilder::new()
+            .account("Account111111111111111111111111111111111111", dump_path)
+            .program(
+                AddressOrKeypair::Keypair("keys/program \"keypair\".json".into()),
+                so_file_path,
+                ProgramLoader::NonUpgradeable,
+            )
+            .account_dir("fixtures/account dir")
+            .build();
+        assert_eq!(
+            args(&options),
+            [
+                "--account",
+                "Account111111111111111111111111111111111111",
+                dump_path,
+                "--bpf-program",
+                "keys/program \"keypair\".json",
+                so_file_path,
+                "--account-dir",
+                "fixtures/account dir",
+            ]
+        );
     }
 }
@@ -21,9 +21,33 @@ use {
 /// The attribute accepts arguments for configuring the test case's startup
 /// behavior. These startup configs are valid for both a `BanksClient` program
 /// test and an `RpcClient` integration/migration test.
+/// * `accounts` is a list of `(address, dump_path)` tuples of JSON account
+///   dumps, as written by `solana account --output json`, to load at genesis.
+/// * `account_dir` is a directory of JSON account dumps to load at genesis.
+/// * `cloned_accounts` is a list of addresses of accounts cloned from a cluster
+///   with `boomerang fixtures clone`, to load at genesis from the workspace's
+///   `fixtures/cloned-accounts` cache without network access.
+/// * `extra_programs` is a list of `(program_file, program_id)` tuples for
+///   additional programs to load alongside the program under test, such as
+///   programs it invokes via CPI.
 /// * `features_disabled` is a list of feature IDs from the Solana SDK's
 ///   `feature_set` to disable on startup. validator before running the test
 ///   case.
+/// * `payer_keypair` is the path to a keypair file to use as the fee payer,
+///   instead of a fresh keypair.
+/// * `payer_lamports` is the number of lamports to fund the fee payer with.
+///   Defaults to 1 SOL.
+/// * `staged_accounts` is the path to a function returning a `Vec<(Pubkey,
+///   AccountSharedData)>` of accounts to set at genesis, for constructing
+///   pre-states such as a deactivated lookup table. The path is resolved from
+///   the test crate's root, e.g. `crate::fixtures::accounts`.
+/// * `validator_start_options` is the path to a function returning a
+///   `Vec<BoomerangTestValidatorStartOptions>` of extra options to start the
+///   test validator with, such as a compute unit limit or ticks per slot, to
+///   mirror a cluster's conditions. Options the validator takes once, such as
+///   an RPC port, replace those Boomerang derives, though ports can only be set
+///   with a `validator_pool_size` of 1. These can be put together with
+///   `BoomerangTestValidatorStartOptionsBuilder`. Program tests ignore them.
 /// * `warp_slot` is the slot to warp the bank or test validator to before
 ///   running the test case.
 #[boomerang::test(warp_slot = 123)]
@@ -33,13 +57,14 @@ pub async fn test_create_lookup_table_idempotent(mut client: BoomerangClient) {
 
     let (create_lookup_table_ix, lookup_table_address) =
         create_lookup_table(authority_address, payer_pubkey, 123);
+    let create_lookup_table_ix = client.retarget_instruction(create_lookup_table_ix);
 
     // First create should succeed
     {
         let transaction =
-            client.create_default_transaction(&mut [create_lookup_table_ix.clone()], &[]);
+            client.create_default_transaction(std::slice::from_ref(&create_lookup_table_ix), &[]);
         client
-            .expect_successful_transaction(transaction)
+            .expect_successful_transaction(transaction, &[])
             .await
             .unwrap();
 
@@ -63,10 +88,10 @@ pub async fn test_create_lookup_table_idempotent(mut client: BoomerangClient) {
     // Second create should succeed too
     {
         let transaction = client
-            .create_default_transaction_with_new_blockhash(&mut [create_lookup_table_ix], &[])
+            .create_default_transaction_with_new_blockhash(&[create_lookup_table_ix], &[])
             .await;
         client
-            .expect_successful_transaction(transaction)
+            .expect_successful_transaction(transaction, &[])
             .await
             .unwrap();
     }
@@ -85,11 +110,14 @@ pub async fn test_create_lookup_table_not_idempotent(mut client: BoomerangClient
 
     let (create_lookup_table_ix, ..) =
         create_lookup_table_signed(authority_address, payer_pubkey, 123);
+    let create_lookup_table_ix = client.retarget_instruction(create_lookup_table_ix);
 
-    let transaction = client
-        .create_default_transaction(&mut [create_lookup_table_ix.clone()], &[&authority_keypair]);
+    let transaction = client.create_default_transaction(
+        std::slice::from_ref(&create_lookup_table_ix),
+        &[&authority_keypair],
+    );
     client
-        .expect_successful_transaction(transaction)
+        .expect_successful_transaction(transaction, &[&authority_keypair])
         .await
         .unwrap();
 
@@ -97,13 +125,14 @@ pub async fn test_create_lookup_table_not_idempotent(mut client: BoomerangClient
     {
         let transaction = client
             .create_default_transaction_with_new_blockhash(
-                &mut [create_lookup_table_ix],
+                &[create_lookup_table_ix],
                 &[&authority_keypair],
             )
             .await;
         client
             .expect_failed_transaction_instruction(
                 transaction,
+                &[&authority_keypair],
                 0,
                 InstructionError::AccountAlreadyInitialized,
             )
@@ -118,10 +147,12 @@ pub async fn test_create_lookup_table_use_payer_as_authority(mut client: Boomera
 
     let (create_lookup_table_ix, ..) =
         create_lookup_table_signed(authority_address, payer_pubkey, 123);
+    let create_lookup_table_ix = client.retarget_instruction(create_lookup_table_ix);
 
-    let transaction = client.create_default_transaction(&mut [create_lookup_table_ix.clone()], &[]);
+    let transaction =
+        client.create_default_transaction(std::slice::from_ref(&create_lookup_table_ix), &[]);
     client
-        .expect_successful_transaction(transaction)
+        .expect_successful_transaction(transaction, &[])
         .await
         .unwrap();
 }
@@ -134,19 +165,26 @@ pub async fn test_create_lookup_table_use_payer_as_authority(mut client: Boomera
 pub async fn test_create_lookup_table_missing_signer(mut client: BoomerangClient) {
     let unsigned_authority_address = Pubkey::new_unique();
 
-    let mut ix = create_lookup_table_signed(
-        unsigned_authority_address,
-        client.fee_payer().pubkey(),
-        Slot::MAX,
-    )
-    .0;
+    let mut ix = client.retarget_instruction(
+        create_lookup_table_signed(
+            unsigned_authority_address,
+            client.fee_payer().pubkey(),
+            Slot::MAX,
+        )
+        .0,
+    );
     ix.accounts[1].is_signer = false;
 
     let tx = client
-        .create_default_transaction_with_new_blockhash(&mut [ix], &[])
+        .create_default_transaction_with_new_blockhash(&[ix], &[])
         .await;
     client
-        .expect_failed_transaction_instruction(tx, 0, InstructionError::MissingRequiredSignature)
+        .expect_failed_transaction_instruction(
+            tx,
+            &[],
+            0,
+            InstructionError::MissingRequiredSignature,
+        )
         .await;
 }
 
@@ -155,13 +193,14 @@ pub async fn test_create_lookup_table_not_recent_slot(mut client: BoomerangClien
     let payer = client.fee_payer();
     let authority_address = Pubkey::new_unique();
 
-    let ix = create_lookup_table(authority_address, payer.pubkey(), Slot::MAX).0;
+    let ix = client
+        .retarget_instruction(create_lookup_table(authority_address, payer.pubkey(), Slot::MAX).0);
 
     let tx = client
-        .create_default_transaction_with_new_blockhash(&mut [ix], &[])
+        .create_default_transaction_with_new_blockhash(&[ix], &[])
         .await;
     client
-        .expect_failed_transaction_instruction(tx, 0, InstructionError::InvalidInstructionData)
+        .expect_failed_transaction_instruction(tx, &[], 0, InstructionError::InvalidInstructionData)
         .await;
 }
 
@@ -170,13 +209,14 @@ pub async fn test_create_lookup_table_pda_mismatch(mut client: BoomerangClient)
     let payer = client.fee_payer();
     let authority_address = Pubkey::new_unique();
 
-    let mut ix = create_lookup_table(authority_address, payer.pubkey(), 123).0;
+    let mut ix =
+        client.retarget_instruction(create_lookup_table(authority_address, payer.pubkey(), 123).0);
     ix.accounts[0].pubkey = Pubkey::new_unique();
 
     let tx = client
-        .create_default_transaction_with_new_blockhash(&mut [ix], &[])
+        .create_default_transaction_with_new_blockhash(&[ix], &[])
         .await;
     client
-        .expect_failed_transaction_instruction(tx, 0, InstructionError::InvalidArgument)
+        .expect_failed_transaction_instruction(tx, &[], 0, InstructionError::InvalidArgument)
         .await;
 }
@@ -15,7 +15,19 @@ use solana_boomerang::boomerang;
 ///   local test validator.
 /// * `migration_tests` is a list of tuples of the form `(source_program_name,
 ///   target)` that declares which native program the declared source program
-///   should be migration tested against.
+///   should be migration tested against. The target is a `NativeProgram`
+///   variant, such as `NativeProgram::AddressLookupTable`.
+/// * `migration_test_mode` is how migration tests exercise the target program.
+///   `MigrationTestMode::BeforeAndAfter` runs them against the native program
+///   first and compares the results with those against the migrated program.
+/// * `validator_pool_size` is the number of test validators to run integration
+///   and replay test chunks on in parallel. Test cases sharing a setup config
+///   form a chunk, so this suite's chunks are split across two validators.
+/// * `differential_tests` is a list of tuples of the form `(program_name,
+///   differential_program_name)` that declares pairs of implementations whose
+///   outcomes are compared transaction by transaction.
+/// * `replay_tests` is a list of program names whose program tests are recorded
+///   and replayed against a test validator.
 #[boomerang::main(
     programs = [
         (
@@ -38,12 +50,21 @@ use solana_boomerang::boomerang;
     migration_tests = [
         (
             "solana_address_lookup_table_program",
-            "NativeProgram::AddressLookupTable"
+            NativeProgram::AddressLookupTable
         ),
         (
             "solana_address_lookup_table_program",
-            "NativeProgram::AddressLookupTable"
+            NativeProgram::AddressLookupTable
         ),
     ],
+    migration_test_mode = MigrationTestMode::BeforeAndAfter,
+    validator_pool_size = 2,
+    differential_tests = [
+        (
+            "solana_address_lookup_table_program",
+            "solana_address_lookup_table_program"
+        ),
+    ],
+    replay_tests = ["solana_address_lookup_table_program"],
 )]
 async fn main() {}